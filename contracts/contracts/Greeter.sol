@@ -6,6 +6,8 @@ import "hardhat/console.sol";
 contract Greeter {
     string private _greeting;
 
+    event GreetingChanged(address indexed sender, string greeting);
+
     constructor(string memory greeting) {
         console.log("Deploying a Greeter with _greeting:", greeting);
         _greeting = greeting;
@@ -18,5 +20,6 @@ contract Greeter {
     function setGreeting(string memory greeting) public {
         console.log("Changing _greeting from '%s' to '%s'", _greeting, greeting);
         _greeting = greeting;
+        emit GreetingChanged(msg.sender, greeting);
     }
 }
@@ -0,0 +1,25 @@
+//SPDX-License-Identifier: MIT OR Apache-2.0
+pragma solidity ^0.8.0;
+
+/// @dev executes a batch of calls in a single call, without reverting if some
+/// of them revert, to run many test vectors with one `eth_call`
+contract Multicall {
+    struct Call {
+        address target;
+        bytes callData;
+    }
+
+    struct Result {
+        bool success;
+        bytes returnData;
+    }
+
+    /// @dev `returnData` is the revert data of the calls that failed
+    function tryAggregate(Call[] memory calls) public returns (Result[] memory results) {
+        results = new Result[](calls.length);
+        for (uint256 i = 0; i < calls.length; i++) {
+            (bool success, bytes memory returnData) = calls[i].target.call(calls[i].callData);
+            results[i] = Result(success, returnData);
+        }
+    }
+}
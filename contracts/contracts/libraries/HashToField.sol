@@ -0,0 +1,119 @@
+// SPDX-License-Identifier: MIT
+
+pragma solidity ^0.8.0;
+
+/// @dev hash_to_field of the IETF hash-to-curve draft
+/// (https://datatracker.ietf.org/doc/html/draft-irtf-cfrg-hash-to-curve), with
+/// expand_message_xmd over Keccak-256 or SHA-256.
+/// Each element is reduced from 64 uniform bytes (L = 64 instead of the
+/// minimal 48 of the draft for k = 128), so that the elements of the 255-bit
+/// Pasta fields are uniform up to a statistical distance of 2^-257.
+library HashToField {
+    // bytes of uniform output per field element
+    uint256 private constant _L = 64;
+    // input block sizes of the hash functions: the rate of Keccak-256, and
+    // the block of SHA-256
+    uint256 private constant _KECCAK_BLOCK_SIZE = 136;
+    uint256 private constant _SHA256_BLOCK_SIZE = 64;
+
+    /// @return expand_message_xmd(message, dst, lenInBytes) with Keccak-256
+    function expandMessageXmd(
+        bytes memory message,
+        bytes memory dst,
+        uint256 lenInBytes
+    ) internal pure returns (bytes memory) {
+        return _expandMessageXmd(message, dst, lenInBytes, false);
+    }
+
+    /// @return expand_message_xmd(message, dst, lenInBytes) with SHA-256
+    function expandMessageXmdSha256(
+        bytes memory message,
+        bytes memory dst,
+        uint256 lenInBytes
+    ) internal pure returns (bytes memory) {
+        return _expandMessageXmd(message, dst, lenInBytes, true);
+    }
+
+    /// @return count elements modulo `modulus` (P_MOD or R_MOD of the curve
+    /// libraries), hashed from message with the domain separation tag dst
+    /// and Keccak-256
+    function hashToField(
+        bytes memory message,
+        bytes memory dst,
+        uint256 count,
+        uint256 modulus
+    ) internal pure returns (uint256[] memory) {
+        return _reduce(_expandMessageXmd(message, dst, count * _L, false), count, modulus);
+    }
+
+    /// @return `hashToField` with SHA-256
+    function hashToFieldSha256(
+        bytes memory message,
+        bytes memory dst,
+        uint256 count,
+        uint256 modulus
+    ) internal pure returns (uint256[] memory) {
+        return _reduce(_expandMessageXmd(message, dst, count * _L, true), count, modulus);
+    }
+
+    function _hash(bytes memory data, bool useSha256) private pure returns (bytes32) {
+        return useSha256 ? sha256(data) : keccak256(data);
+    }
+
+    function _expandMessageXmd(
+        bytes memory message,
+        bytes memory dst,
+        uint256 lenInBytes,
+        bool useSha256
+    ) private pure returns (bytes memory uniform) {
+        uint256 ell = (lenInBytes + 31) / 32;
+        require(
+            ell <= 255 && lenInBytes <= 65535 && dst.length <= 255,
+            "HashToField: invalid length"
+        );
+        // the draft forbids empty tags
+        require(dst.length > 0, "HashToField: empty dst");
+
+        bytes memory dstPrime = abi.encodePacked(dst, uint8(dst.length));
+        bytes memory zPad = new bytes(useSha256 ? _SHA256_BLOCK_SIZE : _KECCAK_BLOCK_SIZE);
+        bytes32 b0 = _hash(
+            abi.encodePacked(zPad, message, uint16(lenInBytes), uint8(0), dstPrime),
+            useSha256
+        );
+
+        uniform = new bytes(ell * 32);
+        bytes32 bi = _hash(abi.encodePacked(b0, uint8(1), dstPrime), useSha256);
+        for (uint256 i = 1; i <= ell; i++) {
+            if (i > 1) {
+                bi = _hash(abi.encodePacked(b0 ^ bi, uint8(i), dstPrime), useSha256);
+            }
+            assembly {
+                mstore(add(uniform, mul(i, 0x20)), bi)
+            }
+        }
+        // truncate to lenInBytes
+        assembly {
+            mstore(uniform, lenInBytes)
+        }
+    }
+
+    /// @dev OS2IP of each chunk of _L bytes, modulo `modulus`
+    function _reduce(
+        bytes memory uniform,
+        uint256 count,
+        uint256 modulus
+    ) private pure returns (uint256[] memory elements) {
+        elements = new uint256[](count);
+        for (uint256 i = 0; i < count; i++) {
+            uint256 hi;
+            uint256 lo;
+            assembly {
+                let chunk := add(add(uniform, 0x20), mul(i, _L))
+                hi := mload(chunk)
+                lo := mload(add(chunk, 0x20))
+            }
+            // hi * 2^256 + lo
+            elements[i] = addmod(mulmod(mulmod(hi, 2**128, modulus), 2**128, modulus), lo, modulus);
+        }
+    }
+}
@@ -0,0 +1,51 @@
+// SPDX-License-Identifier: MIT
+//
+//
+// Copyright 2022 Zhenfei Zhang
+// Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+// The above copyright notice and this permission notice shall be included in all copies or substantial portions of the Software.
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+pragma solidity ^0.8.0;
+
+import {Pallas} from "./Pallas.sol";
+
+/// @dev Public wrappers of the `Pallas` library, to be deployed once and
+/// linked into contracts instead of inlining the internal functions.
+library PallasExternal {
+    function add(Pallas.PallasAffinePoint memory p1, Pallas.PallasAffinePoint memory p2)
+        public
+        view
+        returns (Pallas.PallasAffinePoint memory)
+    {
+        return Pallas.add(p1, p2);
+    }
+
+    function scalarMul(Pallas.PallasAffinePoint memory p, uint256 s)
+        public
+        view
+        returns (Pallas.PallasAffinePoint memory)
+    {
+        return Pallas.scalarMul(p, s);
+    }
+
+    function multiScalarMul(Pallas.PallasAffinePoint[] memory bases, uint256[] memory scalars)
+        public
+        view
+        returns (Pallas.PallasAffinePoint memory)
+    {
+        return Pallas.multiScalarMul(bases, scalars);
+    }
+
+    function validateCurvePoint(Pallas.PallasAffinePoint memory point) public pure {
+        Pallas.validateCurvePoint(point);
+    }
+
+    function validateCurvePointOrInfinity(Pallas.PallasAffinePoint memory point) public pure {
+        Pallas.validateCurvePointOrInfinity(point);
+    }
+
+    function validateNonInfinity(Pallas.PallasAffinePoint memory point) public pure {
+        Pallas.validateNonInfinity(point);
+    }
+}
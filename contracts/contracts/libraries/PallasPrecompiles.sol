@@ -0,0 +1,96 @@
+//SPDX-License-Identifier: MIT OR Apache-2.0
+pragma solidity ^0.8.0;
+
+import {Pallas as C} from "./Pallas.sol";
+
+/// @dev Pallas operations through the prototype precompiles of the in-process
+/// EVM (`pasta-precompiles` feature of the rust crate), falling back to the
+/// `Pallas` library where they are missing, e.g. on geth and Hardhat.
+/// Points are encoded as in EIP-196, with (0, 0) as point of infinity.
+library PallasPrecompiles {
+    /// @dev input (x1, y1, x2, y2), output (x, y)
+    address internal constant ADD = address(uint160(0x100));
+    /// @dev input (x, y, s), output (x, y)
+    address internal constant SCALAR_MUL = address(uint160(0x101));
+    /// @dev input (x_i, y_i, s_i) for each term, output (x, y)
+    address internal constant MULTI_SCALAR_MUL = address(uint160(0x102));
+
+    /// @dev gas of the precompiles, given to them as a stipend: a failing
+    /// precompile consumes all the gas of its call, which must be left to the
+    /// fallback
+    uint256 internal constant ADD_GAS = 150;
+    uint256 internal constant SCALAR_MUL_GAS = 6000;
+    /// @dev per term
+    uint256 internal constant MULTI_SCALAR_MUL_GAS = 6000;
+
+    /// @dev `found` is false if the precompile is missing, a call to an
+    /// account without code succeeds without output, or if it failed within
+    /// `stipend`
+    function _call(address precompile, bytes memory input, uint256 stipend)
+        private
+        view
+        returns (bool found, C.PallasAffinePoint memory r)
+    {
+        uint256 x;
+        uint256 y;
+        assembly {
+            let success := staticcall(stipend, precompile, add(input, 0x20), mload(input), 0x00, 0x40)
+            found := and(success, eq(returndatasize(), 0x40))
+            x := mload(0x00)
+            y := mload(0x20)
+        }
+        r = C.PallasAffinePoint(x, y);
+    }
+
+    function add(C.PallasAffinePoint memory p1, C.PallasAffinePoint memory p2)
+        internal
+        view
+        returns (C.PallasAffinePoint memory)
+    {
+        (bool found, C.PallasAffinePoint memory r) = _call(
+            ADD,
+            abi.encode(p1.x, p1.y, p2.x, p2.y),
+            ADD_GAS
+        );
+        return found ? r : C.add(p1, p2);
+    }
+
+    function scalarMul(C.PallasAffinePoint memory p, uint256 s)
+        internal
+        view
+        returns (C.PallasAffinePoint memory)
+    {
+        (bool found, C.PallasAffinePoint memory r) = _call(
+            SCALAR_MUL,
+            abi.encode(p.x, p.y, s),
+            SCALAR_MUL_GAS
+        );
+        return found ? r : C.scalarMul(p, s);
+    }
+
+    function multiScalarMul(C.PallasAffinePoint[] memory bases, uint256[] memory scalars)
+        internal
+        view
+        returns (C.PallasAffinePoint memory)
+    {
+        require(scalars.length == bases.length, "MSM error: length does not match");
+
+        bytes memory input = new bytes(bases.length * 0x60);
+        for (uint256 i = 0; i < bases.length; i++) {
+            C.PallasAffinePoint memory p = bases[i];
+            uint256 s = scalars[i];
+            assembly {
+                let ptr := add(add(input, 0x20), mul(i, 0x60))
+                mstore(ptr, mload(p))
+                mstore(add(ptr, 0x20), mload(add(p, 0x20)))
+                mstore(add(ptr, 0x40), s)
+            }
+        }
+        (bool found, C.PallasAffinePoint memory r) = _call(
+            MULTI_SCALAR_MUL,
+            input,
+            MULTI_SCALAR_MUL_GAS * bases.length
+        );
+        return found ? r : C.multiScalarMul(bases, scalars);
+    }
+}
@@ -24,6 +24,15 @@ library Vesta {
     uint256 private constant _THREE_OVER_TWO =
         14474011154664524427946373126085988481681528240970823689839871374196681474050;
 
+    // phi(x, y) = (BETA * x, y) is an endomorphism of the curve, with
+    // phi(P) = LAMBDA * P. BETA and LAMBDA are cube roots of unity in F_p and F_r.
+    uint256 public constant BETA =
+        2942865608506852014473558576493638302197734138389222805617480874486368177743;
+    uint256 public constant LAMBDA =
+        8503465768106391777493614032514048814691664078728891710322960303815233784505;
+    // bound of the halves of the GLV decomposition of a scalar
+    int256 private constant _GLV_BOUND = 2**128;
+
     struct VestaAffinePoint {
         uint256 x;
         uint256 y;
@@ -72,9 +81,11 @@ library Vesta {
             return VestaAffinePoint(0, 0);
         }
 
-        uint256 x = invert(point.z, P_MOD);
-        uint256 y = mulmod(point.y, x, P_MOD);
-        x = mulmod(point.x, x, P_MOD);
+        // (X, Y, Z) are Jacobian coordinates: x = X/Z^2, y = Y/Z^3
+        uint256 zInv = invert(point.z, P_MOD);
+        uint256 zInv2 = mulmod(zInv, zInv, P_MOD);
+        uint256 x = mulmod(point.x, zInv2, P_MOD);
+        uint256 y = mulmod(point.y, mulmod(zInv2, zInv, P_MOD), P_MOD);
 
         return VestaAffinePoint(x, y);
     }
@@ -99,7 +110,7 @@ library Vesta {
         assembly {
             let x := mload(point)
             let y := mload(add(point, 0x20))
-            let z := mload(add(point, 0x20))
+            let z := mload(add(point, 0x40))
             result := and(and(iszero(x), iszero(y)), iszero(z))
         }
     }
@@ -129,6 +140,18 @@ library Vesta {
         return R_MOD - (fr % R_MOD);
     }
 
+    /// @return phi(p) = (BETA * x, y), which is LAMBDA * p
+    function endomorphism(VestaAffinePoint memory p)
+        internal
+        pure
+        returns (VestaAffinePoint memory)
+    {
+        if (isInfinity(p)) {
+            return p;
+        }
+        return VestaAffinePoint(mulmod(BETA, p.x, P_MOD), p.y);
+    }
+
     /// @return 2*point
     function double(VestaProjectivePoint memory point)
         internal
@@ -319,6 +342,8 @@ library Vesta {
             if (s1 == s2) {
                 return double(p1);
             }
+            // p2 == -p1
+            return VestaProjectivePoint(0, 0, 0);
         }
 
         assembly {
@@ -360,24 +385,15 @@ library Vesta {
 
     /// @return r the product of a VestaAffinePoint and a scalar, i.e.
     /// p == p.mul(1) and p.add(p) == p.mul(2) for all VestaAffinePoints p.
+    /// The scalar is used as is (raw policy), see `scalarMulStrict` and
+    /// `scalarMulReduce`.
     function scalarMul(VestaAffinePoint memory p, uint256 s)
         internal
         view
         returns (VestaAffinePoint memory r)
     {
-        uint256 bit;
-        uint256 i = 0;
-        VestaAffinePoint memory tmp = p;
-        r = VestaAffinePoint(0, 0);
-
-        for (i = 0; i < 256; i++) {
-            bit = s & 1;
-            s /= 2;
-            if (bit == 1) {
-                r = add(r, tmp);
-            }
-            tmp = double(tmp);
-        }
+        // in Jacobian coordinates, with a single inversion at the end
+        return IntoAffine(scalarMul(IntoProjective(p), s));
     }
 
     /// @return r the product of a VestaProjectivePoint and a scalar, i.e.
@@ -402,6 +418,188 @@ library Vesta {
         }
     }
 
+    /// @dev Straus-Shamir double-scalar multiplication, with a single chain of
+    /// doublings and a 4-entry table (O, p, q, p + q)
+    /// @return r = a * p + b * q
+    function doubleScalarMul(
+        VestaAffinePoint memory p,
+        uint256 a,
+        VestaAffinePoint memory q,
+        uint256 b
+    ) internal view returns (VestaAffinePoint memory r) {
+        return IntoAffine(doubleScalarMul(IntoProjective(p), a, IntoProjective(q), b));
+    }
+
+    /// @dev Straus-Shamir double-scalar multiplication, with a single chain of
+    /// doublings and a 4-entry table (O, p, q, p + q)
+    /// @return r = a * p + b * q
+    function doubleScalarMul(
+        VestaProjectivePoint memory p,
+        uint256 a,
+        VestaProjectivePoint memory q,
+        uint256 b
+    ) internal pure returns (VestaProjectivePoint memory r) {
+        VestaProjectivePoint[] memory points = new VestaProjectivePoint[](2);
+        uint256[] memory scalars = new uint256[](2);
+        points[0] = p;
+        points[1] = q;
+        scalars[0] = a;
+        scalars[1] = b;
+        return _straus(points, scalars);
+    }
+
+    /// @dev `doubleScalarMul` with the GLV decompositions of a and b as hint
+    /// (`k[0] + k[1] * LAMBDA` and `k[2] + k[3] * LAMBDA`, see `scalarMulWithHint`),
+    /// computing four products with half-size scalars and a 16-entry table.
+    /// @return r = a * p + b * q
+    function doubleScalarMulWithHint(
+        VestaAffinePoint memory p,
+        uint256 a,
+        VestaAffinePoint memory q,
+        uint256 b,
+        int256[4] memory k
+    ) internal view returns (VestaAffinePoint memory r) {
+        VestaProjectivePoint[] memory points = new VestaProjectivePoint[](4);
+        uint256[] memory halves = new uint256[](4);
+        (halves[0], halves[1]) = _glvHalves(a, k[0], k[1]);
+        (halves[2], halves[3]) = _glvHalves(b, k[2], k[3]);
+        (points[0], points[1]) = _glvPoints(p, k[0], k[1]);
+        (points[2], points[3]) = _glvPoints(q, k[2], k[3]);
+        r = IntoAffine(_straus(points, halves));
+    }
+
+    /// @dev Straus' multi-scalar multiplication, with a single chain of
+    /// doublings and a table of the 2^n sums of subsets of the n points
+    /// @return r = \sum scalars[i] * points[i]
+    function _straus(VestaProjectivePoint[] memory points, uint256[] memory scalars)
+        private
+        pure
+        returns (VestaProjectivePoint memory r)
+    {
+        // table[j] is the sum of the points[i] for the bits i set in j
+        VestaProjectivePoint[] memory table = new VestaProjectivePoint[](1 << points.length);
+        table[0] = VestaProjectivePoint(0, 0, 0);
+        uint256 bits = 0;
+        for (uint256 i = 0; i < points.length; i++) {
+            uint256 size = 1 << i;
+            for (uint256 j = 0; j < size; j++) {
+                table[size + j] = add(table[j], points[i]);
+            }
+            bits |= scalars[i];
+        }
+
+        uint256 length = 0;
+        while (length < 256 && bits >> length != 0) {
+            length++;
+        }
+        r = VestaProjectivePoint(0, 0, 0);
+        for (; length > 0; length--) {
+            r = double(r);
+            uint256 index = 0;
+            for (uint256 i = 0; i < points.length; i++) {
+                index |= ((scalars[i] >> (length - 1)) & 1) << i;
+            }
+            if (index != 0) {
+                r = add(r, table[index]);
+            }
+        }
+    }
+
+    // Scalar policies: `scalarMul` and `multiScalarMul` use the scalars as
+    // they are (raw), so scalars >= r give the product by s mod r after a
+    // full 256-bit chain. The strict variants revert on scalars >= r, the
+    // reducing variants multiply by s mod r.
+
+    /// @return r the product of p and s, revert if s >= r
+    function scalarMulStrict(VestaAffinePoint memory p, uint256 s)
+        internal
+        view
+        returns (VestaAffinePoint memory r)
+    {
+        validateScalarField(s);
+        return scalarMul(p, s);
+    }
+
+    /// @return r the product of p and s mod r
+    function scalarMulReduce(VestaAffinePoint memory p, uint256 s)
+        internal
+        view
+        returns (VestaAffinePoint memory r)
+    {
+        return scalarMul(p, s % R_MOD);
+    }
+
+    /// @return r the product of p and s, s is not reduced nor validated
+    function scalarMulRaw(VestaAffinePoint memory p, uint256 s)
+        internal
+        view
+        returns (VestaAffinePoint memory r)
+    {
+        return scalarMul(p, s);
+    }
+
+    /// @return r the product of p and s, revert if s >= r
+    function scalarMulStrict(VestaProjectivePoint memory p, uint256 s)
+        internal
+        pure
+        returns (VestaProjectivePoint memory r)
+    {
+        validateScalarField(s);
+        return scalarMul(p, s);
+    }
+
+    /// @return r the product of p and s mod r
+    function scalarMulReduce(VestaProjectivePoint memory p, uint256 s)
+        internal
+        pure
+        returns (VestaProjectivePoint memory r)
+    {
+        return scalarMul(p, s % R_MOD);
+    }
+
+    /// @return r the product of p and s, s is not reduced nor validated
+    function scalarMulRaw(VestaProjectivePoint memory p, uint256 s)
+        internal
+        pure
+        returns (VestaProjectivePoint memory r)
+    {
+        return scalarMul(p, s);
+    }
+
+    /// @dev `multiScalarMul`, revert if any scalar is >= r
+    function multiScalarMulStrict(VestaAffinePoint[] memory bases, uint256[] memory scalars)
+        internal
+        view
+        returns (VestaAffinePoint memory r)
+    {
+        for (uint256 i = 0; i < scalars.length; i++) {
+            validateScalarField(scalars[i]);
+        }
+        return multiScalarMul(bases, scalars);
+    }
+
+    /// @dev `multiScalarMul` of the scalars mod r, `scalars` is left unchanged
+    function multiScalarMulReduce(VestaAffinePoint[] memory bases, uint256[] memory scalars)
+        internal
+        view
+        returns (VestaAffinePoint memory r)
+    {
+        uint256[] memory reduced = new uint256[](scalars.length);
+        for (uint256 i = 0; i < scalars.length; i++) {
+            reduced[i] = scalars[i] % R_MOD;
+        }
+        return multiScalarMul(bases, reduced);
+    }
+
+    /// @dev `multiScalarMul`, the scalars are not reduced nor validated
+    function multiScalarMulRaw(VestaAffinePoint[] memory bases, uint256[] memory scalars)
+        internal
+        view
+        returns (VestaAffinePoint memory r)
+    {
+        return multiScalarMul(bases, scalars);
+    }
+
     /// @dev Multi-scalar Mulitiplication (MSM)
     /// @return r = \Prod{B_i^s_i} where {s_i} are `scalars` and {B_i} are `bases`
     function multiScalarMul(VestaAffinePoint[] memory bases, uint256[] memory scalars)
@@ -411,10 +609,11 @@ library Vesta {
     {
         require(scalars.length == bases.length, "MSM error: length does not match");
 
-        r = scalarMul(bases[0], scalars[0]);
+        VestaProjectivePoint memory acc = scalarMul(IntoProjective(bases[0]), scalars[0]);
         for (uint256 i = 1; i < scalars.length; i++) {
-            r = add(r, scalarMul(bases[i], scalars[i]));
+            acc = add(acc, scalarMul(IntoProjective(bases[i]), scalars[i]));
         }
+        r = IntoAffine(acc);
     }
 
     /// @dev Compute f^-1 for f \in Fr scalar field
@@ -435,29 +634,231 @@ library Vesta {
         require(success, "Pallas: pow precompile failed!");
     }
 
+    // Hint-assisted variants: the caller computes an expensive value off-chain
+    // (see the `hints` module of the Rust crate) and the library only checks
+    // it, reverting on a bad hint.
+
+    /// @dev `invert` with the inverse as hint, checked with one mulmod instead
+    /// of the pow precompile
+    /// @return the inverse of f modulo `modulus`
+    function invertWithHint(
+        uint256 f,
+        uint256 inverse,
+        uint256 modulus
+    ) internal pure returns (uint256) {
+        require(mulmod(f, inverse, modulus) == 1, "Vesta: invalid inverse hint");
+        return inverse % modulus;
+    }
+
+    /// @dev `add` with the slope of the line through p1 and p2 (the tangent if
+    /// p1 == p2) as hint instead of an inversion. p1 and p2 must be valid
+    /// points, the hint is ignored if p1, p2 or the sum is the point of infinity.
+    /// @return the sum of two VestaAffinePoints
+    function addWithHint(
+        VestaAffinePoint memory p1,
+        VestaAffinePoint memory p2,
+        uint256 lambda
+    ) internal pure returns (VestaAffinePoint memory) {
+        if (isInfinity(p1)) {
+            return p2;
+        }
+
+        if (isInfinity(p2)) {
+            return p1;
+        }
+
+        uint256 x1 = p1.x;
+        uint256 y1 = p1.y;
+        uint256 x2 = p2.x;
+        uint256 y2 = p2.y;
+        bool isValid;
+        if (x1 == x2) {
+            if (y1 != y2) {
+                // p2 == -p1
+                return VestaAffinePoint(0, 0);
+            }
+            // lambda * 2y = 3x^2
+            isValid =
+                mulmod(lambda, addmod(y1, y1, P_MOD), P_MOD) ==
+                mulmod(3, mulmod(x1, x1, P_MOD), P_MOD);
+        } else {
+            // lambda * (x2 - x1) = y2 - y1
+            isValid =
+                mulmod(lambda, addmod(x2, P_MOD - x1, P_MOD), P_MOD) ==
+                addmod(y2, P_MOD - y1, P_MOD);
+        }
+        require(isValid, "Vesta: invalid slope hint");
+
+        uint256 x3;
+        uint256 y3;
+        assembly {
+            // x3 = lambda^2 - x1 - x2
+            x3 := mulmod(lambda, lambda, P_MOD)
+            x3 := addmod(x3, sub(mul(P_MOD, 2), add(x1, x2)), P_MOD)
+
+            // y3 = lambda * (x1 - x3) - y1
+            y3 := mulmod(lambda, add(x1, sub(P_MOD, x3)), P_MOD)
+            y3 := addmod(y3, sub(P_MOD, y1), P_MOD)
+        }
+
+        return VestaAffinePoint(x3, y3);
+    }
+
+    /// @dev GLV scalar multiplication, with the decomposition
+    /// s = k1 + k2 * LAMBDA mod r as hint, where |k1|, |k2| < 2^128. It computes
+    /// k1 * p + k2 * phi(p) with half the doublings of `scalarMul`.
+    /// @return r the product of a VestaAffinePoint and a scalar
+    function scalarMulWithHint(
+        VestaAffinePoint memory p,
+        uint256 s,
+        int256 k1,
+        int256 k2
+    ) internal view returns (VestaAffinePoint memory r) {
+        (uint256 a1, uint256 a2) = _glvHalves(s, k1, k2);
+        (VestaProjectivePoint memory q1, VestaProjectivePoint memory q2) = _glvPoints(p, k1, k2);
+        r = IntoAffine(doubleScalarMul(q1, a1, q2, a2));
+    }
+
+    /// @return a1 = |k1| and a2 = |k2|, where (k1, k2) is the GLV decomposition
+    /// hint of s, revert if it is out of bounds or s != k1 + k2 * LAMBDA mod r
+    function _glvHalves(
+        uint256 s,
+        int256 k1,
+        int256 k2
+    ) private pure returns (uint256 a1, uint256 a2) {
+        require(
+            k1 > -_GLV_BOUND && k1 < _GLV_BOUND && k2 > -_GLV_BOUND && k2 < _GLV_BOUND,
+            "Vesta: invalid GLV hint"
+        );
+        a1 = k1 < 0 ? uint256(-k1) : uint256(k1);
+        a2 = k2 < 0 ? uint256(-k2) : uint256(k2);
+        require(
+            addmod(
+                k1 < 0 ? R_MOD - a1 : a1,
+                mulmod(k2 < 0 ? R_MOD - a2 : a2, LAMBDA, R_MOD),
+                R_MOD
+            ) == s % R_MOD,
+            "Vesta: invalid GLV hint"
+        );
+    }
+
+    /// @return q1 = sign(k1) * p and q2 = sign(k2) * phi(p), so that
+    /// k1 * p + k2 * phi(p) = |k1| * q1 + |k2| * q2
+    function _glvPoints(
+        VestaAffinePoint memory p,
+        int256 k1,
+        int256 k2
+    ) private pure returns (VestaProjectivePoint memory q1, VestaProjectivePoint memory q2) {
+        q1 = IntoProjective(k1 < 0 ? negate(p) : p);
+        VestaAffinePoint memory phi = endomorphism(p);
+        q2 = IntoProjective(k2 < 0 ? negate(phi) : phi);
+    }
+
+    /// @dev Decompress the point with x-coordinate x, with a square root y of
+    /// x^3 + 5 as hint. Either root is accepted, `greatest` selects the one
+    /// with y > -y, as `get_point_from_x` of arkworks.
+    function decompressWithHint(
+        uint256 x,
+        bool greatest,
+        uint256 y
+    ) internal pure returns (VestaAffinePoint memory) {
+        require(
+            x < P_MOD &&
+                y < P_MOD &&
+                mulmod(y, y, P_MOD) == addmod(mulmod(x, mulmod(x, x, P_MOD), P_MOD), 5, P_MOD),
+            "Vesta: invalid square root hint"
+        );
+        if ((y > P_MOD - y) != greatest) {
+            y = P_MOD - y;
+        }
+        return VestaAffinePoint(x, y);
+    }
+
     /**
      * validate the following:
-     *   x != 0
-     *   y != 0
      *   x < p
      *   y < p
      *   y^2 = x^3 + 5 mod p
      */
-    /// @dev validate VestaAffinePoint and check if it is on curve
+    /// @dev validate VestaAffinePoint and check if it is on curve. The point of
+    /// infinity (0, 0) is rejected, since it doesn't satisfy the equation;
+    /// `validateCurvePointOrInfinity` accepts it as arkworks' `is_on_curve`
+    /// does. There is no point with x = 0 or y = 0 on Vesta: 5 is not a square
+    /// and -5 is not a cube mod p.
     /// @notice credit: Aztec, Spilsbury Holdings Ltd
     function validateCurvePoint(VestaAffinePoint memory point) internal pure {
-        bool isWellFormed;
+        require(_isOnCurve(point), "Vesta: invalid point");
+    }
+
+    /**
+     * validate the following:
+     *   x < p
+     *   y < p
+     *   z < p
+     *   y^2 = x^3 + 5 * z^6 mod p and z != 0
+     */
+    /// @dev validate VestaProjectivePoint (Jacobian coordinates) and check if
+    /// it is on curve. The point of infinity is rejected.
+    function validateCurvePoint(VestaProjectivePoint memory point) internal pure {
+        require(_isOnCurve(point), "Vesta: invalid point");
+    }
+
+    /// @dev `validateCurvePoint`, but also accepting the point of infinity in
+    /// its (0, 0) representation, as arkworks' `is_on_curve`.
+    function validateCurvePointOrInfinity(VestaAffinePoint memory point) internal pure {
+        require(isInfinity(point) || _isOnCurve(point), "Vesta: invalid point");
+    }
+
+    /// @dev `validateCurvePoint`, but also accepting the point of infinity in
+    /// its (0, 0, 0) representation, the one `isInfinity` recognizes.
+    function validateCurvePointOrInfinity(VestaProjectivePoint memory point) internal pure {
+        require(isInfinity(point) || _isOnCurve(point), "Vesta: invalid point");
+    }
+
+    function _isOnCurve(VestaAffinePoint memory point) private pure returns (bool isWellFormed) {
         uint256 p = P_MOD;
         assembly {
             let x := mload(point)
             let y := mload(add(point, 0x20))
 
             isWellFormed := and(
-                and(and(lt(x, p), lt(y, p)), not(or(iszero(x), iszero(y)))),
+                and(lt(x, p), lt(y, p)),
                 eq(mulmod(y, y, p), addmod(mulmod(x, mulmod(x, x, p), p), 5, p))
             )
         }
-        require(isWellFormed, "Pallas: invalid point");
+    }
+
+    function _isOnCurve(VestaProjectivePoint memory point)
+        private
+        pure
+        returns (bool isWellFormed)
+    {
+        uint256 p = P_MOD;
+        assembly {
+            let x := mload(point)
+            let y := mload(add(point, 0x20))
+            let z := mload(add(point, 0x40))
+            let z2 := mulmod(z, z, p)
+            let z6 := mulmod(z2, mulmod(z2, z2, p), p)
+
+            isWellFormed := and(
+                and(and(lt(x, p), lt(y, p)), and(lt(z, p), iszero(iszero(z)))),
+                eq(
+                    mulmod(y, y, p),
+                    addmod(mulmod(x, mulmod(x, x, p), p), mulmod(5, z6, p), p)
+                )
+            )
+        }
+    }
+
+    /// @dev revert if point is the point of infinity
+    function validateNonInfinity(VestaAffinePoint memory point) internal pure {
+        require(!isInfinity(point), "Vesta: point of infinity");
+    }
+
+    /// @dev revert if point is the point of infinity
+    function validateNonInfinity(VestaProjectivePoint memory point) internal pure {
+        require(!isInfinity(point), "Vesta: point of infinity");
     }
 
     /// @dev Validate scalar field, revert if invalid (namely if fr > r_mod).
@@ -467,9 +868,11 @@ library Vesta {
         assembly {
             isValid := lt(fr, R_MOD)
         }
-        require(isValid, "Pallas: invalid scalar field");
+        require(isValid, "Vesta: invalid scalar field");
     }
 
+    /// @dev Reduce little-endian bytes modulo r. To hash messages into a field,
+    /// use `HashToField` instead, which is uniform and domain separated.
     function fromLeBytesModOrder(bytes memory leBytes) internal pure returns (uint256 ret) {
         // TODO: Can likely be gas optimized by copying the first 31 bytes directly.
         for (uint256 i = 0; i < leBytes.length; i++) {
@@ -478,9 +881,30 @@ library Vesta {
         }
     }
 
-    /// @dev Check if y-coordinate of VestaAffinePoint is negative.
+    // Sign conventions of the y-coordinate, for the point compression of
+    // each ecosystem. y is assumed to be reduced (y < p).
+
+    /// @dev arkworks' sign: y is negative if y < -y, i.e. 0 < y <= (p - 1) / 2.
     function isYNegative(VestaAffinePoint memory point) internal pure returns (bool) {
-        return point.y < P_MOD / 2;
+        return point.y != 0 && point.y <= (P_MOD - 1) / 2;
+    }
+
+    /// @dev The flag of arkworks' compressed points (and the `greatest`
+    /// argument of `get_point_from_x`): y > -y, i.e. y > (p - 1) / 2.
+    function isYGreatest(VestaAffinePoint memory point) internal pure returns (bool) {
+        return point.y > (P_MOD - 1) / 2;
+    }
+
+    /// @dev Mina's convention: y is even, as required of the commitment R of
+    /// Schnorr signatures. The `isOdd` flag of compressed keys is its negation.
+    function isYEven(VestaAffinePoint memory point) internal pure returns (bool) {
+        return point.y & 1 == 0;
+    }
+
+    /// @dev Zcash's sign bit: the parity of y, stored in the most significant
+    /// bit of the 32-byte little-endian encoding of x (as in `pasta_curves`).
+    function yParity(VestaAffinePoint memory point) internal pure returns (uint8) {
+        return uint8(point.y & 1);
     }
 
     // @dev Perform a modular exponentiation.
@@ -0,0 +1,37 @@
+//SPDX-License-Identifier: MIT OR Apache-2.0
+pragma solidity ^0.8.4;
+
+/// @dev reverts in all possible ways, to test the decoding of revert reasons
+contract TestErrors {
+    error InvalidPoint(uint256 x, uint256 y);
+
+    uint256[] private _values;
+    uint256 public counter;
+
+    constructor() {}
+
+    function revertWithMessage(string memory message) public pure {
+        revert(message);
+    }
+
+    function revertWithCustomError(uint256 x, uint256 y) public pure {
+        revert InvalidPoint(x, y);
+    }
+
+    function divide(uint256 a, uint256 b) public pure returns (uint256) {
+        return a / b;
+    }
+
+    function add(uint256 a, uint256 b) public pure returns (uint256) {
+        return a + b;
+    }
+
+    function valueAt(uint256 index) public view returns (uint256) {
+        return _values[index];
+    }
+
+    /// @dev a state changing version of `divide`, to test mined transactions
+    function incrementBy(uint256 a, uint256 b) public {
+        counter += a / b;
+    }
+}
@@ -0,0 +1,31 @@
+//SPDX-License-Identifier: MIT OR Apache-2.0
+pragma solidity ^0.8.0;
+
+import {HashToField} from "../libraries/HashToField.sol";
+
+contract TestHashToField {
+    function expandMessageXmd(
+        bytes memory message,
+        bytes memory dst,
+        uint256 lenInBytes,
+        bool useSha256
+    ) public pure returns (bytes memory) {
+        if (useSha256) {
+            return HashToField.expandMessageXmdSha256(message, dst, lenInBytes);
+        }
+        return HashToField.expandMessageXmd(message, dst, lenInBytes);
+    }
+
+    function hashToField(
+        bytes memory message,
+        bytes memory dst,
+        uint256 count,
+        uint256 modulus,
+        bool useSha256
+    ) public pure returns (uint256[] memory) {
+        if (useSha256) {
+            return HashToField.hashToFieldSha256(message, dst, count, modulus);
+        }
+        return HashToField.hashToField(message, dst, count, modulus);
+    }
+}
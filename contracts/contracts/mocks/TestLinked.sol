@@ -0,0 +1,22 @@
+//SPDX-License-Identifier: MIT OR Apache-2.0
+pragma solidity ^0.8.0;
+
+import {Pallas} from "../libraries/Pallas.sol";
+import {PallasExternal} from "../libraries/PallasExternal.sol";
+import {Vesta} from "../libraries/Vesta.sol";
+import {VestaExternal} from "../libraries/VestaExternal.sol";
+
+/// @dev uses the linked external libraries, points are passed as coordinates
+contract TestLinked {
+    constructor() {}
+
+    function pallasScalarMulGenerator(uint256 s) public view returns (uint256 x, uint256 y) {
+        Pallas.PallasAffinePoint memory p = PallasExternal.scalarMul(Pallas.AffineGenerator(), s);
+        return (p.x, p.y);
+    }
+
+    function vestaScalarMulGenerator(uint256 s) public view returns (uint256 x, uint256 y) {
+        Vesta.VestaAffinePoint memory p = VestaExternal.scalarMul(Vesta.AffineGenerator(), s);
+        return (p.x, p.y);
+    }
+}
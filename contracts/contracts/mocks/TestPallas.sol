@@ -102,6 +102,36 @@ contract TestPallas {
         return C.scalarMul(p, s);
     }
 
+    /// @dev the former affine scalar multiplication, with an inversion in
+    /// every addition and doubling, to compare with `affineScalarMul`
+    function affineScalarMulWithInversions(C.PallasAffinePoint memory p, uint256 s)
+        public
+        view
+        returns (C.PallasAffinePoint memory r)
+    {
+        C.PallasAffinePoint memory tmp = p;
+        r = C.PallasAffinePoint(0, 0);
+
+        for (uint256 i = 0; i < 256; i++) {
+            if (s & 1 == 1) {
+                r = C.add(r, tmp);
+            }
+            s /= 2;
+            tmp = C.double(tmp);
+        }
+    }
+
+    /// @dev the former MSM, on top of `affineScalarMulWithInversions`
+    function multiScalarMulWithInversions(
+        C.PallasAffinePoint[] memory bases,
+        uint256[] memory scalars
+    ) public view returns (C.PallasAffinePoint memory r) {
+        r = affineScalarMulWithInversions(bases[0], scalars[0]);
+        for (uint256 i = 1; i < scalars.length; i++) {
+            r = C.add(r, affineScalarMulWithInversions(bases[i], scalars[i]));
+        }
+    }
+
     function projectiveScalarMul(C.PallasProjectivePoint memory p, uint256 s)
         public
         pure
@@ -110,6 +140,82 @@ contract TestPallas {
         return C.scalarMul(p, s);
     }
 
+    function affineDoubleScalarMul(
+        C.PallasAffinePoint memory p,
+        uint256 a,
+        C.PallasAffinePoint memory q,
+        uint256 b
+    ) public view returns (C.PallasAffinePoint memory) {
+        return C.doubleScalarMul(p, a, q, b);
+    }
+
+    function projectiveDoubleScalarMul(
+        C.PallasProjectivePoint memory p,
+        uint256 a,
+        C.PallasProjectivePoint memory q,
+        uint256 b
+    ) public pure returns (C.PallasProjectivePoint memory) {
+        return C.doubleScalarMul(p, a, q, b);
+    }
+
+    function affineDoubleScalarMulWithHint(
+        C.PallasAffinePoint memory p,
+        uint256 a,
+        C.PallasAffinePoint memory q,
+        uint256 b,
+        int256[4] memory k
+    ) public view returns (C.PallasAffinePoint memory) {
+        return C.doubleScalarMulWithHint(p, a, q, b, k);
+    }
+
+    function affineScalarMulStrict(C.PallasAffinePoint memory p, uint256 s)
+        public
+        view
+        returns (C.PallasAffinePoint memory)
+    {
+        return C.scalarMulStrict(p, s);
+    }
+
+    function affineScalarMulReduce(C.PallasAffinePoint memory p, uint256 s)
+        public
+        view
+        returns (C.PallasAffinePoint memory)
+    {
+        return C.scalarMulReduce(p, s);
+    }
+
+    function affineScalarMulRaw(C.PallasAffinePoint memory p, uint256 s)
+        public
+        view
+        returns (C.PallasAffinePoint memory)
+    {
+        return C.scalarMulRaw(p, s);
+    }
+
+    function multiScalarMulStrict(C.PallasAffinePoint[] memory bases, uint256[] memory scalars)
+        public
+        view
+        returns (C.PallasAffinePoint memory)
+    {
+        return C.multiScalarMulStrict(bases, scalars);
+    }
+
+    function multiScalarMulReduce(C.PallasAffinePoint[] memory bases, uint256[] memory scalars)
+        public
+        view
+        returns (C.PallasAffinePoint memory)
+    {
+        return C.multiScalarMulReduce(bases, scalars);
+    }
+
+    function multiScalarMulRaw(C.PallasAffinePoint[] memory bases, uint256[] memory scalars)
+        public
+        view
+        returns (C.PallasAffinePoint memory)
+    {
+        return C.multiScalarMulRaw(bases, scalars);
+    }
+
     function invertFr(uint256 fr) public view returns (uint256 output) {
         return C.invert(fr, C.R_MOD);
     }
@@ -118,10 +224,62 @@ contract TestPallas {
         return C.invert(fq, C.P_MOD);
     }
 
+    function invertFqWithHint(uint256 fq, uint256 inverse) public pure returns (uint256) {
+        return C.invertWithHint(fq, inverse, C.P_MOD);
+    }
+
+    function affineAddWithHint(
+        C.PallasAffinePoint memory p1,
+        C.PallasAffinePoint memory p2,
+        uint256 lambda
+    ) public pure returns (C.PallasAffinePoint memory) {
+        return C.addWithHint(p1, p2, lambda);
+    }
+
+    function affineScalarMulWithHint(
+        C.PallasAffinePoint memory p,
+        uint256 s,
+        int256 k1,
+        int256 k2
+    ) public view returns (C.PallasAffinePoint memory) {
+        return C.scalarMulWithHint(p, s, k1, k2);
+    }
+
+    function decompressWithHint(
+        uint256 x,
+        bool greatest,
+        uint256 y
+    ) public pure returns (C.PallasAffinePoint memory) {
+        return C.decompressWithHint(x, greatest, y);
+    }
+
     function validateCurvePoint(C.PallasAffinePoint memory point) public pure {
         C.validateCurvePoint(point);
     }
 
+    function validateProjectiveCurvePoint(C.PallasProjectivePoint memory point) public pure {
+        C.validateCurvePoint(point);
+    }
+
+    function validateCurvePointOrInfinity(C.PallasAffinePoint memory point) public pure {
+        C.validateCurvePointOrInfinity(point);
+    }
+
+    function validateProjectiveCurvePointOrInfinity(C.PallasProjectivePoint memory point)
+        public
+        pure
+    {
+        C.validateCurvePointOrInfinity(point);
+    }
+
+    function validateNonInfinity(C.PallasAffinePoint memory point) public pure {
+        C.validateNonInfinity(point);
+    }
+
+    function validateProjectiveNonInfinity(C.PallasProjectivePoint memory point) public pure {
+        C.validateNonInfinity(point);
+    }
+
     function validateScalarField(uint256 fr) public pure {
         C.validateScalarField(fr);
     }
@@ -134,6 +292,18 @@ contract TestPallas {
         return C.isYNegative(p);
     }
 
+    function isYGreatest(C.PallasAffinePoint memory p) public pure returns (bool) {
+        return C.isYGreatest(p);
+    }
+
+    function isYEven(C.PallasAffinePoint memory p) public pure returns (bool) {
+        return C.isYEven(p);
+    }
+
+    function yParity(C.PallasAffinePoint memory p) public pure returns (uint8) {
+        return C.yParity(p);
+    }
+
     function powSmall(
         uint256 base,
         uint256 exponent,
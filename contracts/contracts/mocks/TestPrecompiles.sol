@@ -0,0 +1,61 @@
+//SPDX-License-Identifier: MIT OR Apache-2.0
+pragma solidity ^0.8.0;
+
+import {Pallas} from "../libraries/Pallas.sol";
+import {PallasPrecompiles} from "../libraries/PallasPrecompiles.sol";
+import {Vesta} from "../libraries/Vesta.sol";
+import {VestaPrecompiles} from "../libraries/VestaPrecompiles.sol";
+
+/// @dev the operations of `TestPallas` and `TestVesta` compared in gas with
+/// the precompile prototypes
+contract TestPrecompiles {
+    constructor() {}
+
+    function pallasAdd(Pallas.PallasAffinePoint memory p1, Pallas.PallasAffinePoint memory p2)
+        public
+        view
+        returns (Pallas.PallasAffinePoint memory)
+    {
+        return PallasPrecompiles.add(p1, p2);
+    }
+
+    function pallasScalarMul(Pallas.PallasAffinePoint memory p, uint256 s)
+        public
+        view
+        returns (Pallas.PallasAffinePoint memory)
+    {
+        return PallasPrecompiles.scalarMul(p, s);
+    }
+
+    function pallasMultiScalarMul(Pallas.PallasAffinePoint[] memory bases, uint256[] memory scalars)
+        public
+        view
+        returns (Pallas.PallasAffinePoint memory)
+    {
+        return PallasPrecompiles.multiScalarMul(bases, scalars);
+    }
+
+    function vestaAdd(Vesta.VestaAffinePoint memory p1, Vesta.VestaAffinePoint memory p2)
+        public
+        view
+        returns (Vesta.VestaAffinePoint memory)
+    {
+        return VestaPrecompiles.add(p1, p2);
+    }
+
+    function vestaScalarMul(Vesta.VestaAffinePoint memory p, uint256 s)
+        public
+        view
+        returns (Vesta.VestaAffinePoint memory)
+    {
+        return VestaPrecompiles.scalarMul(p, s);
+    }
+
+    function vestaMultiScalarMul(Vesta.VestaAffinePoint[] memory bases, uint256[] memory scalars)
+        public
+        view
+        returns (Vesta.VestaAffinePoint memory)
+    {
+        return VestaPrecompiles.multiScalarMul(bases, scalars);
+    }
+}
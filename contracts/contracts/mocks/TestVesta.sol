@@ -98,6 +98,36 @@ contract TestVesta {
         return C.scalarMul(p, s);
     }
 
+    /// @dev the former affine scalar multiplication, with an inversion in
+    /// every addition and doubling, to compare with `affineScalarMul`
+    function affineScalarMulWithInversions(C.VestaAffinePoint memory p, uint256 s)
+        public
+        view
+        returns (C.VestaAffinePoint memory r)
+    {
+        C.VestaAffinePoint memory tmp = p;
+        r = C.VestaAffinePoint(0, 0);
+
+        for (uint256 i = 0; i < 256; i++) {
+            if (s & 1 == 1) {
+                r = C.add(r, tmp);
+            }
+            s /= 2;
+            tmp = C.double(tmp);
+        }
+    }
+
+    /// @dev the former MSM, on top of `affineScalarMulWithInversions`
+    function multiScalarMulWithInversions(
+        C.VestaAffinePoint[] memory bases,
+        uint256[] memory scalars
+    ) public view returns (C.VestaAffinePoint memory r) {
+        r = affineScalarMulWithInversions(bases[0], scalars[0]);
+        for (uint256 i = 1; i < scalars.length; i++) {
+            r = C.add(r, affineScalarMulWithInversions(bases[i], scalars[i]));
+        }
+    }
+
     function projectiveScalarMul(C.VestaProjectivePoint memory p, uint256 s)
         public
         pure
@@ -106,6 +136,82 @@ contract TestVesta {
         return C.scalarMul(p, s);
     }
 
+    function affineDoubleScalarMul(
+        C.VestaAffinePoint memory p,
+        uint256 a,
+        C.VestaAffinePoint memory q,
+        uint256 b
+    ) public view returns (C.VestaAffinePoint memory) {
+        return C.doubleScalarMul(p, a, q, b);
+    }
+
+    function projectiveDoubleScalarMul(
+        C.VestaProjectivePoint memory p,
+        uint256 a,
+        C.VestaProjectivePoint memory q,
+        uint256 b
+    ) public pure returns (C.VestaProjectivePoint memory) {
+        return C.doubleScalarMul(p, a, q, b);
+    }
+
+    function affineDoubleScalarMulWithHint(
+        C.VestaAffinePoint memory p,
+        uint256 a,
+        C.VestaAffinePoint memory q,
+        uint256 b,
+        int256[4] memory k
+    ) public view returns (C.VestaAffinePoint memory) {
+        return C.doubleScalarMulWithHint(p, a, q, b, k);
+    }
+
+    function affineScalarMulStrict(C.VestaAffinePoint memory p, uint256 s)
+        public
+        view
+        returns (C.VestaAffinePoint memory)
+    {
+        return C.scalarMulStrict(p, s);
+    }
+
+    function affineScalarMulReduce(C.VestaAffinePoint memory p, uint256 s)
+        public
+        view
+        returns (C.VestaAffinePoint memory)
+    {
+        return C.scalarMulReduce(p, s);
+    }
+
+    function affineScalarMulRaw(C.VestaAffinePoint memory p, uint256 s)
+        public
+        view
+        returns (C.VestaAffinePoint memory)
+    {
+        return C.scalarMulRaw(p, s);
+    }
+
+    function multiScalarMulStrict(C.VestaAffinePoint[] memory bases, uint256[] memory scalars)
+        public
+        view
+        returns (C.VestaAffinePoint memory)
+    {
+        return C.multiScalarMulStrict(bases, scalars);
+    }
+
+    function multiScalarMulReduce(C.VestaAffinePoint[] memory bases, uint256[] memory scalars)
+        public
+        view
+        returns (C.VestaAffinePoint memory)
+    {
+        return C.multiScalarMulReduce(bases, scalars);
+    }
+
+    function multiScalarMulRaw(C.VestaAffinePoint[] memory bases, uint256[] memory scalars)
+        public
+        view
+        returns (C.VestaAffinePoint memory)
+    {
+        return C.multiScalarMulRaw(bases, scalars);
+    }
+
     function invertFr(uint256 fr) public view returns (uint256 output) {
         return C.invert(fr, C.R_MOD);
     }
@@ -114,10 +220,62 @@ contract TestVesta {
         return C.invert(fq, C.P_MOD);
     }
 
+    function invertFqWithHint(uint256 fq, uint256 inverse) public pure returns (uint256) {
+        return C.invertWithHint(fq, inverse, C.P_MOD);
+    }
+
+    function affineAddWithHint(
+        C.VestaAffinePoint memory p1,
+        C.VestaAffinePoint memory p2,
+        uint256 lambda
+    ) public pure returns (C.VestaAffinePoint memory) {
+        return C.addWithHint(p1, p2, lambda);
+    }
+
+    function affineScalarMulWithHint(
+        C.VestaAffinePoint memory p,
+        uint256 s,
+        int256 k1,
+        int256 k2
+    ) public view returns (C.VestaAffinePoint memory) {
+        return C.scalarMulWithHint(p, s, k1, k2);
+    }
+
+    function decompressWithHint(
+        uint256 x,
+        bool greatest,
+        uint256 y
+    ) public pure returns (C.VestaAffinePoint memory) {
+        return C.decompressWithHint(x, greatest, y);
+    }
+
     function validateCurvePoint(C.VestaAffinePoint memory point) public pure {
         C.validateCurvePoint(point);
     }
 
+    function validateProjectiveCurvePoint(C.VestaProjectivePoint memory point) public pure {
+        C.validateCurvePoint(point);
+    }
+
+    function validateCurvePointOrInfinity(C.VestaAffinePoint memory point) public pure {
+        C.validateCurvePointOrInfinity(point);
+    }
+
+    function validateProjectiveCurvePointOrInfinity(C.VestaProjectivePoint memory point)
+        public
+        pure
+    {
+        C.validateCurvePointOrInfinity(point);
+    }
+
+    function validateNonInfinity(C.VestaAffinePoint memory point) public pure {
+        C.validateNonInfinity(point);
+    }
+
+    function validateProjectiveNonInfinity(C.VestaProjectivePoint memory point) public pure {
+        C.validateNonInfinity(point);
+    }
+
     function validateScalarField(uint256 fr) public pure {
         C.validateScalarField(fr);
     }
@@ -130,6 +288,18 @@ contract TestVesta {
         return C.isYNegative(p);
     }
 
+    function isYGreatest(C.VestaAffinePoint memory p) public pure returns (bool) {
+        return C.isYGreatest(p);
+    }
+
+    function isYEven(C.VestaAffinePoint memory p) public pure returns (bool) {
+        return C.isYEven(p);
+    }
+
+    function yParity(C.VestaAffinePoint memory p) public pure returns (uint8) {
+        return C.yParity(p);
+    }
+
     function powSmall(
         uint256 base,
         uint256 exponent,
@@ -0,0 +1,214 @@
+//! Compile the contracts under `contracts/contracts` with solc and generate
+//! the rust bindings of the contracts in `BINDINGS`.
+//!
+//! solc is taken from `SOLC_PATH` (set by the nix shell), or from the `PATH`.
+//! Imports of npm packages, e.g. `hardhat/console.sol`, are read from
+//! `contracts/node_modules`. Artifacts of all contracts are written in the
+//! Hardhat format to `$OUT_DIR/artifacts`, the solc input and output (with
+//! the source maps) as a Hardhat build info to
+//! `$OUT_DIR/artifacts/build-info/solc.json`, the bindings to
+//! `$OUT_DIR/bindings.rs`.
+#[path = "src/solc.rs"]
+mod solc;
+
+use ethers_contract_abigen::Abigen;
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Fully qualified names of the contracts with rust bindings.
+const BINDINGS: [&str; 6] = [
+    "contracts/mocks/TestPallas.sol:TestPallas",
+    "contracts/mocks/TestVesta.sol:TestVesta",
+    "contracts/Greeter.sol:Greeter",
+    "contracts/mocks/TestLinked.sol:TestLinked",
+    "contracts/mocks/TestErrors.sol:TestErrors",
+    "contracts/mocks/TestHashToField.sol:TestHashToField",
+];
+
+fn sol_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            sol_files(&path, files)?;
+        } else if path.extension().map_or(false, |ext| ext == "sol") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Paths of the import directives of a solidity source.
+fn imports(source: &str) -> Vec<String> {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("import"))
+        .filter_map(|line| line.split(|c| c == '"' || c == '\'').nth(1))
+        .map(String::from)
+        .collect()
+}
+
+/// Source unit name of `import` in the source unit `importer`.
+fn resolve(importer: &str, import: &str) -> String {
+    if !import.starts_with('.') {
+        return import.to_string();
+    }
+    let mut parts: Vec<&str> = importer.split('/').collect();
+    parts.pop();
+    for part in import.split('/') {
+        match part {
+            "." => {}
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+/// Content of the source unit `name`, looked up in `root` and in its
+/// `node_modules` (e.g. `hardhat/console.sol`).
+fn read_source(root: &Path, name: &str) -> Result<String> {
+    for path in &[root.join(name), root.join("node_modules").join(name)] {
+        if path.is_file() {
+            println!("cargo:rerun-if-changed={}", path.display());
+            return Ok(fs::read_to_string(path)?);
+        }
+    }
+    Err(format!(
+        "Unable to find solidity source {} in {:?}, are the npm packages installed?",
+        name, root
+    )
+    .into())
+}
+
+/// All sources under `root/contracts` and their imports, by source unit name.
+fn collect_sources(root: &Path) -> Result<BTreeMap<String, String>> {
+    let mut files = Vec::new();
+    sol_files(&root.join("contracts"), &mut files)?;
+    let mut pending: Vec<String> = files
+        .iter()
+        .map(|f| {
+            f.strip_prefix(root)
+                .unwrap()
+                .to_string_lossy()
+                .replace('\\', "/")
+        })
+        .collect();
+
+    let mut sources = BTreeMap::new();
+    while let Some(name) = pending.pop() {
+        if sources.contains_key(&name) {
+            continue;
+        }
+        let content = read_source(root, &name)?;
+        pending.extend(imports(&content).iter().map(|i| resolve(&name, i)));
+        sources.insert(name, content);
+    }
+    Ok(sources)
+}
+
+/// The solc standard JSON input compiling `sources`.
+fn standard_json_input(sources: &BTreeMap<String, String>) -> Result<Value> {
+    let runs: u64 = match env::var("SOLC_OPTIMIZER_RUNS") {
+        Ok(val) => val.parse()?,
+        Err(_) => 200,
+    };
+    let input = json!({
+        "language": "Solidity",
+        "sources": sources
+            .iter()
+            .map(|(name, content)| (name.clone(), json!({ "content": content })))
+            .collect::<serde_json::Map<_, _>>(),
+        "settings": {
+            "optimizer": { "enabled": true, "runs": runs },
+            "outputSelection": {
+                "*": { "*": ["abi", "evm.bytecode", "evm.deployedBytecode", "metadata"] }
+            }
+        }
+    });
+    Ok(input)
+}
+
+fn write_artifacts(output: &Value, dir: &Path) -> Result<()> {
+    let contracts = output["contracts"].as_object().into_iter().flatten();
+    for (source_name, contracts) in contracts {
+        for (name, contract) in contracts.as_object().into_iter().flatten() {
+            let bytecode = &contract["evm"]["bytecode"];
+            let deployed_bytecode = &contract["evm"]["deployedBytecode"];
+            let artifact = json!({
+                "_format": "hh-sol-artifact-1",
+                "contractName": name,
+                "sourceName": source_name,
+                "abi": contract["abi"],
+                "bytecode": format!("0x{}", bytecode["object"].as_str().unwrap_or_default()),
+                "deployedBytecode":
+                    format!("0x{}", deployed_bytecode["object"].as_str().unwrap_or_default()),
+                "linkReferences": bytecode["linkReferences"],
+                "deployedLinkReferences": deployed_bytecode["linkReferences"],
+                "metadata": contract["metadata"],
+            });
+            let path = dir.join(source_name).join(format!("{}.json", name));
+            fs::create_dir_all(path.parent().unwrap())?;
+            fs::write(path, serde_json::to_string_pretty(&artifact)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write `input` and `output` of solc as a Hardhat build info, which the
+/// profiler reads the source maps from.
+fn write_build_info(input: &Value, output: &Value, dir: &Path) -> Result<()> {
+    let build_info = json!({
+        "_format": "hh-sol-build-info-1",
+        "input": input,
+        "output": output,
+    });
+    fs::create_dir_all(dir.join("build-info"))?;
+    fs::write(
+        dir.join("build-info").join("solc.json"),
+        build_info.to_string(),
+    )?;
+    Ok(())
+}
+
+fn write_bindings(output: &Value, out_dir: &Path) -> Result<()> {
+    let mut bindings = fs::File::create(out_dir.join("bindings.rs"))?;
+    for fully_qualified_name in BINDINGS.iter() {
+        let (source_name, name) = fully_qualified_name.rsplit_once(':').unwrap();
+        let abi = &output["contracts"][source_name][name]["abi"];
+        let abi_path = out_dir.join(format!("{}.abi.json", name));
+        fs::write(&abi_path, abi.to_string())?;
+
+        Abigen::new(name, abi_path.to_string_lossy())?
+            .add_event_derive("serde::Deserialize")
+            .add_event_derive("serde::Serialize")
+            .generate()?
+            .write(&mut bindings)?;
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let out_dir = PathBuf::from(env::var("OUT_DIR")?);
+    println!(
+        "cargo:rerun-if-changed={}",
+        root.join("contracts").display()
+    );
+    println!("cargo:rerun-if-env-changed=SOLC_PATH");
+    println!("cargo:rerun-if-env-changed=SOLC_OPTIMIZER_RUNS");
+
+    let input = standard_json_input(&collect_sources(&root)?)?;
+    let output = solc::compile_standard_json(&input, None)?;
+    write_artifacts(&output, &out_dir.join("artifacts"))?;
+    write_build_info(&input, &output, &out_dir.join("artifacts"))?;
+    write_bindings(&output, &out_dir)
+}
@@ -0,0 +1,235 @@
+//! Pool of funded accounts, so that many tests can run concurrently against
+//! one node.
+//!
+//! Every call to `funded_account` hands out a distinct account with its own
+//! nonce manager. If `MNEMONIC` is set, accounts are derived from it at
+//! indices `ACCOUNT_INDEX_OFFSET + base + 1`, `ACCOUNT_INDEX_OFFSET + base +
+//! 2`, ..., otherwise random accounts are created. `base` partitions the
+//! indices between test binaries (e.g. the lib tests and `tests/*.rs`), which
+//! cargo may run concurrently against the same node: it is derived from the
+//! name of the binary, so that each one derives the same accounts on every
+//! run but not those of the others.
+//!
+//! All accounts are funded by a single funding account: the first unlocked
+//! account of the node, or the account at index `ACCOUNT_INDEX_OFFSET` of
+//! `MNEMONIC` on nodes without unlocked accounts. Funding transactions are
+//! serialized by a coordinator which also tracks the funder's nonce.
+use crate::ethereum::{
+    boxed, parse_env, prepare_tx, BoxedError, DeployConfig, EthereumError, FundingSnafu,
+    InvalidWalletSnafu, RpcSnafu,
+};
+use ethers::{
+    core::k256::ecdsa::SigningKey,
+    prelude::{
+        coins_bip39::English, Address, BlockNumber, Http, LocalWallet, Middleware, MnemonicBuilder,
+        NonceManagerMiddleware, PendingTransaction, Provider, Signer, SignerMiddleware,
+        TransactionRequest, Wallet, U256,
+    },
+    types::transaction::eip2718::TypedTransaction,
+    utils::keccak256,
+};
+use lazy_static::lazy_static;
+use snafu::ResultExt;
+use std::{
+    convert::TryFrom,
+    env,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::Mutex;
+
+type Result<T, E = EthereumError> = std::result::Result<T, E>;
+
+/// Middleware of the accounts handed out by the pool.
+pub type EthMiddleware =
+    NonceManagerMiddleware<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>;
+
+/// Index of the next account derived from `MNEMONIC`, relative to the base
+/// of the binary. Index 0 is reserved for the funding account.
+static NEXT_INDEX: AtomicU32 = AtomicU32::new(1);
+
+/// Accounts of each test binary.
+const PARTITION_SIZE: u32 = 1 << 16;
+/// Number of partitions, such that indices stay below the hardened ones
+/// (`2^31`).
+const PARTITIONS: u32 = 1 << 14;
+
+lazy_static! {
+    /// Next nonce of the funding account, if it is derived from `MNEMONIC`.
+    /// The lock is held while a funding transaction is being sent.
+    static ref FUNDER_NONCE: Mutex<Option<U256>> = Mutex::new(None);
+}
+
+/// HTTP provider for `RPC_URL`, defaults to `http://localhost:8545`.
+///
+/// NOTE: providers must not be shared between tests, every `#[tokio::test]`
+/// runs its own runtime which owns the provider's connections.
+pub fn provider() -> Result<Provider<Http>> {
+    let provider = parse_env("RPC_URL", |val| Provider::<Http>::try_from(val))?
+        .unwrap_or_else(|| Provider::<Http>::try_from("http://localhost:8545").unwrap());
+
+    // Set a lower polling interval to avoid very slow tests
+    Ok(provider.interval(Duration::from_millis(100u64)))
+}
+
+fn index_offset() -> Result<u32> {
+    Ok(parse_env("ACCOUNT_INDEX_OFFSET", |val| val.parse())?.unwrap_or(0))
+}
+
+/// First index, relative to `ACCOUNT_INDEX_OFFSET`, of the partition of the
+/// test binary `binary`. Cargo suffixes the names of test binaries with a hash
+/// of their build, which is ignored so that rebuilds keep their accounts.
+fn partition_base(binary: &str) -> u32 {
+    let name = match binary.rsplit_once('-') {
+        Some((name, hash)) if hash.chars().all(|c| c.is_ascii_hexdigit()) => name,
+        _ => binary,
+    };
+    let hash = keccak256(name.as_bytes());
+    let partition = u32::from_be_bytes([hash[0], hash[1], hash[2], hash[3]]) % PARTITIONS;
+    partition * PARTITION_SIZE
+}
+
+/// `partition_base` of the running binary.
+fn index_base() -> u32 {
+    let binary = env::current_exe()
+        .ok()
+        .and_then(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+        .unwrap_or_default();
+    partition_base(&binary)
+}
+
+/// The wallet at `index` of the BIP-39 `mnemonic`.
+pub fn wallet_at(mnemonic: &str, index: u32) -> Result<LocalWallet> {
+    MnemonicBuilder::<English>::default()
+        .phrase(mnemonic)
+        .index(index)
+        .and_then(|builder| builder.build())
+        .context(InvalidWalletSnafu)
+}
+
+/// Send `config.funding` to `to` from the funding account, unless `to`
+/// already holds at least that amount.
+pub async fn fund(provider: &Provider<Http>, to: Address, config: &DeployConfig) -> Result<()> {
+    try_fund(provider, to, config)
+        .await
+        .context(FundingSnafu { address: to })
+}
+
+async fn try_fund(
+    provider: &Provider<Http>,
+    to: Address,
+    config: &DeployConfig,
+) -> Result<(), BoxedError> {
+    if provider.get_balance(to, None).await? >= config.funding {
+        return Ok(());
+    }
+
+    let mut tx: TypedTransaction = TransactionRequest::new()
+        .to(to)
+        .value(config.funding)
+        .into();
+    let accounts = provider.get_accounts().await?;
+
+    let tx_hash = {
+        let mut nonce = FUNDER_NONCE.lock().await;
+        if let Some(&unlocked) = accounts.first() {
+            // the node assigns nonces of unlocked accounts
+            tx.set_from(unlocked);
+            prepare_tx(provider, &mut tx, config.tx_type, None).await?;
+            *provider.send_transaction(tx, None).await?
+        } else {
+            let mnemonic = env::var("MNEMONIC").map_err(|_| {
+                "the node has no unlocked accounts, set MNEMONIC to fund test accounts"
+            })?;
+            let chain_id = provider.get_chainid().await?.as_u64();
+            let funder = wallet_at(&mnemonic, index_offset()?)?.with_chain_id(chain_id);
+            let client = SignerMiddleware::new(provider.clone(), funder.clone());
+
+            let next = match *nonce {
+                Some(n) => n,
+                None => {
+                    provider
+                        .get_transaction_count(funder.address(), Some(BlockNumber::Pending.into()))
+                        .await?
+                }
+            };
+            tx.set_from(funder.address());
+            tx.set_nonce(next);
+            prepare_tx(&client, &mut tx, config.tx_type, None).await?;
+            match client.send_transaction(tx, None).await {
+                Ok(pending) => {
+                    *nonce = Some(next + 1);
+                    *pending
+                }
+                Err(e) => {
+                    // resync with the node on the next funding
+                    *nonce = None;
+                    return Err(boxed(e));
+                }
+            }
+        }
+    };
+
+    PendingTransaction::new(tx_hash, provider)
+        .await?
+        .ok_or_else(|| format!("funding transaction {:?} was dropped", tx_hash))?;
+    Ok(())
+}
+
+/// A new funded account from the pool.
+pub async fn funded_account(config: &DeployConfig) -> Result<Arc<EthMiddleware>> {
+    let provider = provider()?;
+    let chain_id = provider
+        .get_chainid()
+        .await
+        .map_err(boxed)
+        .context(RpcSnafu {
+            method: "eth_chainId",
+        })?
+        .as_u64();
+
+    let wallet = match env::var("MNEMONIC") {
+        Ok(val) => {
+            let next = NEXT_INDEX.fetch_add(1, Ordering::SeqCst);
+            assert!(
+                next < PARTITION_SIZE,
+                "out of accounts for this test binary"
+            );
+            let index = index_offset()? + index_base() + next;
+            wallet_at(&val, index)?
+        }
+        Err(_) => LocalWallet::new(&mut rand::thread_rng()),
+    }
+    .with_chain_id(chain_id);
+    let address = wallet.address();
+
+    fund(&provider, address, config).await?;
+
+    Ok(Arc::new(NonceManagerMiddleware::new(
+        SignerMiddleware::new(provider, wallet),
+        address,
+    )))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn partitions() {
+        // rebuilds of a binary keep its accounts
+        assert_eq!(
+            partition_base("pasta_rust_sandbox-0123456789abcdef"),
+            partition_base("pasta_rust_sandbox-fedcba9876543210")
+        );
+        assert_ne!(
+            partition_base("pasta_rust_sandbox-0123456789abcdef"),
+            partition_base("ethereum_test-0123456789abcdef")
+        );
+        assert_eq!(partition_base("ethereum_test") % PARTITION_SIZE, 0);
+        assert!(u64::from(partition_base("ethereum_test")) + u64::from(PARTITION_SIZE) <= 1 << 31);
+    }
+}
@@ -0,0 +1,375 @@
+//! Contract artifacts as written by Hardhat (`artifacts/`) and Foundry
+//! (`out/`), located by fully qualified name, e.g.
+//! `contracts/libraries/Pallas.sol:Pallas`.
+//!
+//! Artifacts are searched in `ARTIFACTS_DIR` if set, otherwise in the
+//! artifacts compiled by `build.rs`, then in `../artifacts` (Hardhat) and
+//! `../out` (Foundry), relative to the crate directory rather than to the
+//! working directory.
+use crate::ethereum::{
+    boxed, ArtifactNotFoundSnafu, BoxedError, EthereumError, InvalidNameSnafu, ParseArtifactSnafu,
+    ReadArtifactSnafu,
+};
+use ethers::{abi::Abi, prelude::artifacts::BytecodeObject};
+use serde::Deserialize;
+use snafu::{OptionExt, ResultExt};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+type Result<T, E = EthereumError> = std::result::Result<T, E>;
+
+/// Default artifact directories of `build.rs`, Hardhat and Foundry.
+const DEFAULT_ARTIFACTS_DIRS: [&str; 3] = [
+    concat!(env!("OUT_DIR"), "/artifacts"),
+    concat!(env!("CARGO_MANIFEST_DIR"), "/../artifacts"),
+    concat!(env!("CARGO_MANIFEST_DIR"), "/../out"),
+];
+
+/// Link references of a bytecode: source file -> library name -> offsets.
+pub type LinkReferences = BTreeMap<String, BTreeMap<String, serde_json::Value>>;
+
+/// A compiled contract.
+#[derive(Clone, Debug)]
+pub struct Artifact {
+    pub contract_name: String,
+    /// source file of the contract, e.g. `contracts/libraries/Pallas.sol`,
+    /// if the artifact records it
+    pub source_name: Option<String>,
+    pub abi: Abi,
+    /// creation code, unlinked if the contract uses external libraries
+    pub bytecode: BytecodeObject,
+    pub link_references: LinkReferences,
+    /// runtime code, unlinked if the contract uses external libraries
+    pub deployed_bytecode: BytecodeObject,
+    pub deployed_link_references: LinkReferences,
+    /// solc metadata of the contract, if available
+    pub metadata: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HardhatArtifact {
+    contract_name: String,
+    source_name: String,
+    abi: Abi,
+    bytecode: String,
+    deployed_bytecode: String,
+    #[serde(default)]
+    link_references: LinkReferences,
+    #[serde(default)]
+    deployed_link_references: LinkReferences,
+    /// not written by Hardhat itself, but by `build.rs`
+    #[serde(default)]
+    metadata: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FoundryBytecode {
+    object: String,
+    #[serde(default)]
+    link_references: LinkReferences,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FoundryArtifact {
+    abi: Abi,
+    bytecode: FoundryBytecode,
+    deployed_bytecode: FoundryBytecode,
+    /// an object in recent versions, a JSON string in older ones
+    #[serde(default)]
+    metadata: Option<serde_json::Value>,
+    #[serde(default)]
+    raw_metadata: Option<String>,
+}
+
+fn parse_bytecode(bytecode: &str) -> Result<BytecodeObject, BoxedError> {
+    let trimmed = bytecode.trim().trim_start_matches("0x");
+    Ok(serde_json::from_value(serde_json::json!(trimmed))?)
+}
+
+/// Parse metadata given either as JSON object or as JSON string.
+fn parse_metadata(metadata: serde_json::Value) -> Result<serde_json::Value, BoxedError> {
+    match metadata {
+        serde_json::Value::String(s) => Ok(serde_json::from_str(&s)?),
+        v => Ok(v),
+    }
+}
+
+fn read_json(path: &Path) -> Result<serde_json::Value> {
+    let file = fs::File::open(path).context(ReadArtifactSnafu { path })?;
+    serde_json::from_reader(file)
+        .map_err(boxed)
+        .context(ParseArtifactSnafu { path })
+}
+
+/// Metadata of a Hardhat artifact, taken from the build info referenced by
+/// its `.dbg.json` file. `None` if any of these files is missing.
+fn hardhat_metadata(
+    path: &Path,
+    source_name: &str,
+    contract_name: &str,
+) -> Option<serde_json::Value> {
+    let dbg: serde_json::Value =
+        serde_json::from_reader(fs::File::open(path.with_extension("dbg.json")).ok()?).ok()?;
+    let build_info_path = path.parent()?.join(dbg["buildInfo"].as_str()?);
+    let build_info: serde_json::Value =
+        serde_json::from_reader(fs::File::open(build_info_path).ok()?).ok()?;
+    let metadata = &build_info["output"]["contracts"][source_name][contract_name]["metadata"];
+    parse_metadata(metadata.clone()).ok()
+}
+
+impl Artifact {
+    /// Load a Hardhat or Foundry artifact file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = read_json(path)?;
+        let parsed = if json["bytecode"].is_object() {
+            Self::from_foundry(path, json)
+        } else {
+            Self::from_hardhat(path, json)
+        };
+        parsed.context(ParseArtifactSnafu { path })
+    }
+
+    fn from_hardhat(path: &Path, json: serde_json::Value) -> Result<Self, BoxedError> {
+        let artifact: HardhatArtifact = serde_json::from_value(json)?;
+        let metadata = match artifact.metadata {
+            Some(metadata) => Some(parse_metadata(metadata)?),
+            None => hardhat_metadata(path, &artifact.source_name, &artifact.contract_name),
+        };
+        Ok(Self {
+            metadata,
+            bytecode: parse_bytecode(&artifact.bytecode)?,
+            deployed_bytecode: parse_bytecode(&artifact.deployed_bytecode)?,
+            contract_name: artifact.contract_name,
+            source_name: Some(artifact.source_name),
+            abi: artifact.abi,
+            link_references: artifact.link_references,
+            deployed_link_references: artifact.deployed_link_references,
+        })
+    }
+
+    fn from_foundry(path: &Path, json: serde_json::Value) -> Result<Self, BoxedError> {
+        let artifact: FoundryArtifact = serde_json::from_value(json)?;
+        let metadata = match (artifact.metadata, artifact.raw_metadata) {
+            (Some(metadata), _) => Some(parse_metadata(metadata)?),
+            (None, Some(raw)) => Some(serde_json::from_str(&raw)?),
+            (None, None) => None,
+        };
+        // the compilation target is the only place recording the source path
+        let source_name = metadata
+            .as_ref()
+            .and_then(|m| m["settings"]["compilationTarget"].as_object())
+            .and_then(|target| target.keys().next().cloned());
+        let contract_name = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or("artifact file name is not valid UTF-8")?
+            .to_string();
+
+        Ok(Self {
+            contract_name,
+            source_name,
+            abi: artifact.abi,
+            bytecode: parse_bytecode(&artifact.bytecode.object)?,
+            link_references: artifact.bytecode.link_references,
+            deployed_bytecode: parse_bytecode(&artifact.deployed_bytecode.object)?,
+            deployed_link_references: artifact.deployed_bytecode.link_references,
+            metadata,
+        })
+    }
+
+    /// Artifact of a contract in the output of `solc --standard-json`, i.e.
+    /// `output["contracts"][source_name][contract_name]`.
+    pub(crate) fn from_standard_json(
+        source_name: &str,
+        contract_name: &str,
+        contract: &serde_json::Value,
+    ) -> Result<Self, BoxedError> {
+        let bytecode = &contract["evm"]["bytecode"];
+        let deployed_bytecode = &contract["evm"]["deployedBytecode"];
+        let object = |bytecode: &serde_json::Value| -> Result<BytecodeObject, BoxedError> {
+            bytecode["object"]
+                .as_str()
+                .map(parse_bytecode)
+                .ok_or("no bytecode in solc output")?
+        };
+        Ok(Self {
+            contract_name: contract_name.to_string(),
+            source_name: Some(source_name.to_string()),
+            abi: serde_json::from_value(contract["abi"].clone())?,
+            bytecode: object(bytecode)?,
+            link_references: serde_json::from_value(bytecode["linkReferences"].clone())?,
+            deployed_bytecode: object(deployed_bytecode)?,
+            deployed_link_references: serde_json::from_value(
+                deployed_bytecode["linkReferences"].clone(),
+            )?,
+            metadata: match &contract["metadata"] {
+                serde_json::Value::Null => None,
+                metadata => Some(parse_metadata(metadata.clone())?),
+            },
+        })
+    }
+
+    /// Locate and load the artifact of `fully_qualified_name`.
+    pub fn find(fully_qualified_name: &str) -> Result<Self> {
+        Self::load(&find(fully_qualified_name)?)
+    }
+
+    /// `source_name:contract_name`, if the source name is known.
+    pub fn fully_qualified_name(&self) -> Option<String> {
+        self.source_name
+            .as_ref()
+            .map(|source| format!("{}:{}", source, self.contract_name))
+    }
+}
+
+/// Directories searched for artifacts.
+pub fn artifacts_dirs() -> Vec<PathBuf> {
+    match env::var("ARTIFACTS_DIR") {
+        Ok(val) => vec![PathBuf::from(val)],
+        Err(_) => DEFAULT_ARTIFACTS_DIRS.iter().map(PathBuf::from).collect(),
+    }
+}
+
+/// Path of the artifact of `fully_qualified_name`, e.g.
+/// `contracts/libraries/Pallas.sol:Pallas`.
+///
+/// Hardhat keeps the source directories (`contracts/libraries/Pallas.sol/`),
+/// Foundry only the file name (`Pallas.sol/`).
+pub fn find(fully_qualified_name: &str) -> Result<PathBuf> {
+    find_in(&artifacts_dirs(), fully_qualified_name)
+}
+
+fn find_in(dirs: &[PathBuf], fully_qualified_name: &str) -> Result<PathBuf> {
+    let (source, name) = fully_qualified_name
+        .rsplit_once(':')
+        .context(InvalidNameSnafu {
+            name: fully_qualified_name,
+        })?;
+    let file_name = format!("{}.json", name);
+
+    let mut searched = Vec::new();
+    for dir in dirs {
+        searched.push(dir.join(source).join(&file_name));
+        if let Some(source_file) = Path::new(source).file_name() {
+            searched.push(dir.join(source_file).join(&file_name));
+        }
+    }
+    match searched.iter().find(|path| path.is_file()) {
+        Some(path) => Ok(path.clone()),
+        None => ArtifactNotFoundSnafu {
+            name: fully_qualified_name,
+            searched,
+        }
+        .fail(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write_json(dir: &Path, file: &str, json: serde_json::Value) -> PathBuf {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, json.to_string()).unwrap();
+        path
+    }
+
+    #[test]
+    fn hardhat_and_foundry_artifacts() {
+        let dir = env::temp_dir().join(format!("pasta-artifacts-{}", std::process::id()));
+        let placeholder = "__$0123456789abcdef0123456789abcdef01$__";
+        let link_references = serde_json::json!({
+            "contracts/libraries/PallasExternal.sol": {
+                "PallasExternal": [{ "length": 20, "start": 1 }]
+            }
+        });
+
+        let hardhat = write_json(
+            &dir,
+            "artifacts/contracts/mocks/TestLinked.sol/TestLinked.json",
+            serde_json::json!({
+                "_format": "hh-sol-artifact-1",
+                "contractName": "TestLinked",
+                "sourceName": "contracts/mocks/TestLinked.sol",
+                "abi": [],
+                "bytecode": format!("0x73{}00", placeholder),
+                "deployedBytecode": "0x6000",
+                "linkReferences": link_references,
+                "deployedLinkReferences": {}
+            }),
+        );
+        write_json(
+            &dir,
+            "artifacts/build-info/1.json",
+            serde_json::json!({
+                "output": { "contracts": { "contracts/mocks/TestLinked.sol": {
+                    "TestLinked": { "metadata": "{\"language\":\"Solidity\"}" }
+                } } }
+            }),
+        );
+        write_json(
+            &dir,
+            "artifacts/contracts/mocks/TestLinked.sol/TestLinked.dbg.json",
+            serde_json::json!({ "buildInfo": "../../../build-info/1.json" }),
+        );
+
+        let artifact = Artifact::load(&hardhat).unwrap();
+        assert_eq!(
+            artifact.fully_qualified_name().unwrap(),
+            "contracts/mocks/TestLinked.sol:TestLinked"
+        );
+        assert!(matches!(artifact.bytecode, BytecodeObject::Unlinked(_)));
+        assert_eq!(artifact.link_references.len(), 1);
+        match artifact.deployed_bytecode {
+            BytecodeObject::Bytecode(ref code) => assert_eq!(code.as_ref(), &[0x60, 0x00]),
+            _ => panic!("deployed bytecode is not linked"),
+        }
+        assert_eq!(artifact.metadata.unwrap()["language"], "Solidity");
+
+        let foundry = write_json(
+            &dir,
+            "out/TestLinked.sol/TestLinked.json",
+            serde_json::json!({
+                "abi": [],
+                "bytecode": {
+                    "object": format!("0x73{}00", placeholder),
+                    "linkReferences": link_references
+                },
+                "deployedBytecode": { "object": "0x6000", "linkReferences": {} },
+                "metadata": {
+                    "settings": {
+                        "compilationTarget": { "contracts/mocks/TestLinked.sol": "TestLinked" }
+                    }
+                }
+            }),
+        );
+        let artifact = Artifact::load(&foundry).unwrap();
+        assert_eq!(
+            artifact.fully_qualified_name().unwrap(),
+            "contracts/mocks/TestLinked.sol:TestLinked"
+        );
+        assert!(matches!(artifact.bytecode, BytecodeObject::Unlinked(_)));
+        assert_eq!(artifact.link_references.len(), 1);
+
+        // lookup by fully qualified name in either layout
+        let name = "contracts/mocks/TestLinked.sol:TestLinked";
+        assert_eq!(find_in(&[dir.join("out")], name).unwrap(), foundry);
+        assert_eq!(
+            find_in(&[dir.join("artifacts"), dir.join("out")], name).unwrap(),
+            hardhat
+        );
+        assert!(matches!(
+            find_in(&[dir.join("artifacts")], "contracts/Missing.sol:Missing"),
+            Err(EthereumError::ArtifactNotFound { .. })
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,44 +1,676 @@
 //! naive assertion matcher for `ContractCall.call()` and `ContractCall.send()` tx.
-use ethers::{abi::Detokenize, prelude::*};
+//!
+//! Reverts are decoded into `Revert`s: `Error(string)`, `Panic(uint256)` or
+//! custom errors. The revert data is found by scanning the error for hex
+//! strings, since the JSON-RPC error carrying it is buried in the error type
+//! of the middleware.
+//!
+//! Mined transactions, pending or as receipts, can be checked for their gas
+//! cost and the events they emit.
+use ethers::{
+    abi::{self, ParamType, Token},
+    prelude::*,
+};
+// the matchers are only used by the tests, `Revert` also by `multicall` and
+// `local_evm`
+#[cfg(test)]
+use crate::{artifacts::Artifact, gas::GasMeasurement};
+#[cfg(test)]
+use anyhow::anyhow;
+#[cfg(test)]
+use async_trait::async_trait;
+#[cfg(test)]
+use ethers::{
+    abi::{Detokenize, RawLog},
+    types::transaction::eip2718::TypedTransaction,
+    utils::keccak256,
+};
+#[cfg(test)]
 use std::fmt::Debug;
 
+/// Selector of `Error(string)`.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of `Panic(uint256)`.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Codes of `Panic(uint256)`, see
+/// <https://docs.soliditylang.org/en/v0.8.10/control-structures.html#panic-via-assert-and-error-via-require>
+#[cfg(test)]
+pub(crate) mod panic_code {
+    pub const ARITHMETIC_OVERFLOW: u64 = 0x11;
+    pub const DIVISION_BY_ZERO: u64 = 0x12;
+    pub const ARRAY_OUT_OF_BOUNDS: u64 = 0x32;
+}
+
+/// A Solidity custom error, e.g. `error InvalidPoint(uint256 x, uint256 y)`,
+/// decoded from the tokens of its arguments. Its parameter types, hence its
+/// signature and selector, are read from its declaration in the ABI of
+/// `CONTRACT`.
+#[cfg(test)]
+pub(crate) trait CustomError: Detokenize + Debug {
+    /// fully qualified name of a contract declaring the error, e.g.
+    /// `contracts/mocks/TestErrors.sol:TestErrors`
+    const CONTRACT: &'static str;
+    /// name of the error, e.g. `InvalidPoint`
+    const NAME: &'static str;
+
+    /// Parameter types of the error in the ABI of `CONTRACT`. Panics if the
+    /// artifact is not found or doesn't declare the error exactly once.
+    fn param_types() -> Vec<ParamType> {
+        let artifact = Artifact::find(Self::CONTRACT)
+            .unwrap_or_else(|e| panic!("No artifact of {}: {}", Self::CONTRACT, e));
+        match artifact.abi.errors.get(Self::NAME).map(Vec::as_slice) {
+            Some([error]) => error.inputs.iter().map(|p| p.kind.clone()).collect(),
+            _ => panic!(
+                "{} doesn't declare the error {} exactly once",
+                Self::CONTRACT,
+                Self::NAME
+            ),
+        }
+    }
+
+    /// canonical signature, e.g. `InvalidPoint(uint256,uint256)`
+    fn signature() -> String {
+        let types: Vec<String> = Self::param_types()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        format!("{}({})", Self::NAME, types.join(","))
+    }
+
+    fn selector() -> [u8; 4] {
+        let hash = keccak256(Self::signature().as_bytes());
+        [hash[0], hash[1], hash[2], hash[3]]
+    }
+
+    /// Decode the ABI encoded arguments of the error.
+    fn decode(data: &[u8]) -> Option<Self> {
+        Self::from_tokens(abi::decode(&Self::param_types(), data).ok()?).ok()
+    }
+}
+
+/// Reason of a revert.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Revert {
+    /// `Error(string)`, from `require(..., "message")` and `revert("message")`
+    Error(String),
+    /// `Panic(uint256)`, see `panic_code`
+    Panic(U256),
+    /// custom error, `data` are its ABI encoded arguments
+    Custom { selector: [u8; 4], data: Vec<u8> },
+    /// no revert data found, with the error message
+    Unknown(String),
+}
+
+/// All byte strings written as `0x...` in `s`.
+#[cfg(test)]
+fn hex_strings(s: &str) -> Vec<Vec<u8>> {
+    s.match_indices("0x")
+        .filter_map(|(i, _)| {
+            let digits: String = s[i + 2..]
+                .chars()
+                .take_while(|c| c.is_ascii_hexdigit())
+                .collect();
+            hex::decode(digits).ok()
+        })
+        .collect()
+}
+
+impl Revert {
+    /// Decode revert data, `None` if it is too short to have a selector.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < 4 {
+            return None;
+        }
+        let (selector, args) = data.split_at(4);
+        if selector == &ERROR_SELECTOR[..] {
+            match abi::decode(&[ParamType::String], args).ok()?.pop()? {
+                Token::String(message) => Some(Self::Error(message)),
+                _ => None,
+            }
+        } else if selector == &PANIC_SELECTOR[..] {
+            match abi::decode(&[ParamType::Uint(256)], args).ok()?.pop()? {
+                Token::Uint(code) => Some(Self::Panic(code)),
+                _ => None,
+            }
+        } else {
+            let mut custom = [0u8; 4];
+            custom.copy_from_slice(selector);
+            Some(Self::Custom {
+                selector: custom,
+                data: args.to_vec(),
+            })
+        }
+    }
+
+    /// Find and decode the revert data in the (debug formatted) error of a
+    /// call. `None` if the error is not a revert.
+    #[cfg(test)]
+    pub fn from_error(error: &str) -> Option<Self> {
+        if !error.contains("revert") {
+            return None;
+        }
+        // revert data is a selector followed by 32 byte words, which rules
+        // out addresses and hashes
+        let reverts: Vec<Self> = hex_strings(error)
+            .iter()
+            .filter(|data| data.len() >= 4 && (data.len() - 4) % 32 == 0)
+            .filter_map(|data| Self::decode(data))
+            .collect();
+        reverts
+            .iter()
+            .find(|r| !matches!(r, Self::Custom { .. }))
+            .or_else(|| reverts.first())
+            .cloned()
+            .or_else(|| Some(Self::Unknown(error.to_string())))
+    }
+
+    /// The custom error `E`, if this is one.
+    #[cfg(test)]
+    pub fn custom<E: CustomError>(&self) -> Option<E> {
+        match self {
+            Self::Custom { selector, data } if *selector == E::selector() => E::decode(data),
+            _ => None,
+        }
+    }
+}
+
+/// Revert of a mined transaction, found by replaying it with `eth_call` on the
+/// state before its block. `None` if the transaction succeeded.
+#[cfg(test)]
+pub(crate) async fn mined_revert<M: Middleware>(
+    client: &M,
+    receipt: &TransactionReceipt,
+) -> anyhow::Result<Option<Revert>> {
+    if receipt.status == Some(U64::from(1)) {
+        return Ok(None);
+    }
+    let tx = client
+        .get_transaction(receipt.transaction_hash)
+        .await?
+        .ok_or_else(|| anyhow!("Transaction {:?} not found", receipt.transaction_hash))?;
+    let block = receipt
+        .block_number
+        .ok_or_else(|| anyhow!("Transaction {:?} is not mined", receipt.transaction_hash))?;
+
+    let call: TypedTransaction = TransactionRequest {
+        from: Some(tx.from),
+        to: tx.to.map(Into::into),
+        gas: Some(tx.gas),
+        value: Some(tx.value),
+        data: Some(tx.input),
+        ..Default::default()
+    }
+    .into();
+    let parent = BlockNumber::Number((block.as_u64() - 1).into());
+    Ok(Some(match client.call(&call, Some(parent.into())).await {
+        Ok(_) => Revert::Unknown("the transaction failed, but its replay succeeds".to_string()),
+        Err(e) => {
+            let error = format!("{} {:?}", e, e);
+            Revert::from_error(&error).unwrap_or(Revert::Unknown(error))
+        }
+    }))
+}
+
+/// Outcome of a call or transaction that may have reverted.
+#[cfg(test)]
+pub(crate) trait Reverted {
+    /// The revert, `None` if it succeeded. Panics on errors other than
+    /// reverts.
+    fn reverted(self) -> Option<Revert>;
+}
+
+#[cfg(test)]
+impl<D, M: Middleware> Reverted for Result<D, ContractError<M>> {
+    fn reverted(self) -> Option<Revert> {
+        match self {
+            Ok(_) => None,
+            Err(e) => match Revert::from_error(&format!("{} {:?}", e, e)) {
+                Some(revert) => Some(revert),
+                None => panic!("Tx failed without reverting: {}", e),
+            },
+        }
+    }
+}
+
+/// Result of `mined_revert`.
+#[cfg(test)]
+impl Reverted for Option<Revert> {
+    fn reverted(self) -> Option<Revert> {
+        self
+    }
+}
+
+#[cfg(test)]
 pub(crate) trait Matcher {
     fn should_not_revert(self);
-    fn should_revert(self);
+    fn should_revert(self) -> Revert;
     fn should_revert_with_message(self, message: &str);
+    fn should_revert_with_panic(self, code: u64);
+    /// Returns the decoded custom error for further checks.
+    fn should_revert_with<E: CustomError>(self) -> E;
 }
 
+#[cfg(test)]
 fn check_contains(string: &str, sub_string: &str) {
     if !string.contains(sub_string) {
         panic!("Sub-string \"{}\" not found in \"{}\"", sub_string, string);
     }
 }
 
-impl<D, M> Matcher for Result<D, ContractError<M>>
-where
-    D: Detokenize + Debug,
-    M: Middleware,
-{
+#[cfg(test)]
+impl<T: Reverted> Matcher for T {
     fn should_not_revert(self) {
-        if self.is_err() {
-            panic!("Tx should not revert but it reverted");
+        if let Some(revert) = self.reverted() {
+            panic!("Tx should not revert but it reverted: {:?}", revert);
         }
     }
 
-    fn should_revert(self) {
-        if self.is_ok() {
-            panic!("Tx should revert but it did not revert");
+    fn should_revert(self) -> Revert {
+        match self.reverted() {
+            Some(revert) => revert,
+            None => panic!("Tx should revert but it did not revert"),
         }
-        check_contains(&self.unwrap_err().to_string(), "reverted");
     }
 
     fn should_revert_with_message(self, message: &str) {
-        if self.is_ok() {
-            panic!("Tx should revert but it did not revert");
+        match self.should_revert() {
+            Revert::Error(error) | Revert::Unknown(error) => check_contains(&error, message),
+            revert => panic!(
+                "Tx should revert with message \"{}\" but reverted with {:?}",
+                message, revert
+            ),
+        }
+    }
+
+    fn should_revert_with_panic(self, code: u64) {
+        match self.should_revert() {
+            Revert::Panic(c) if c == U256::from(code) => {}
+            revert => panic!(
+                "Tx should panic with code {:#x} but reverted with {:?}",
+                code, revert
+            ),
+        }
+    }
+
+    fn should_revert_with<E: CustomError>(self) -> E {
+        let revert = self.should_revert();
+        match revert.custom::<E>() {
+            Some(error) => error,
+            None => panic!(
+                "Tx should revert with {} but reverted with {:?}",
+                E::signature(),
+                revert
+            ),
+        }
+    }
+}
+
+/// Anything that reports the gas used by a mined transaction.
+#[cfg(test)]
+pub(crate) trait GasUsed {
+    fn gas_used(&self) -> u64;
+}
+
+#[cfg(test)]
+impl GasUsed for TransactionReceipt {
+    fn gas_used(&self) -> u64 {
+        self.gas_used
+            .expect("No gas_used in TX receipt, is the tx mined?")
+            .as_u64()
+    }
+}
+
+#[cfg(test)]
+impl GasUsed for GasMeasurement {
+    fn gas_used(&self) -> u64 {
+        self.used
+    }
+}
+
+/// Gas bounds, returning `self` to chain further checks.
+#[cfg(test)]
+pub(crate) trait GasMatcher: Sized {
+    fn should_cost_at_most(self, gas: u64) -> Self;
+    /// `min` and `max` are inclusive.
+    fn should_cost_between(self, min: u64, max: u64) -> Self;
+}
+
+#[cfg(test)]
+impl<T: GasUsed> GasMatcher for T {
+    fn should_cost_at_most(self, gas: u64) -> Self {
+        let used = self.gas_used();
+        if used > gas {
+            panic!("Tx should cost at most {} gas but used {}", gas, used);
+        }
+        self
+    }
+
+    fn should_cost_between(self, min: u64, max: u64) -> Self {
+        let used = self.gas_used();
+        if used < min || used > max {
+            panic!(
+                "Tx should cost between {} and {} gas but used {}",
+                min, max, used
+            );
+        }
+        self
+    }
+}
+
+/// Events of a receipt.
+#[cfg(test)]
+pub(crate) trait EventMatcher {
+    /// Returns the first event `E` matching `predicate`, for further checks.
+    fn should_emit<E, F>(&self, predicate: F) -> E
+    where
+        E: EthEvent + Debug,
+        F: Fn(&E) -> bool;
+}
+
+#[cfg(test)]
+impl EventMatcher for TransactionReceipt {
+    fn should_emit<E, F>(&self, predicate: F) -> E
+    where
+        E: EthEvent + Debug,
+        F: Fn(&E) -> bool,
+    {
+        let events: Vec<E> = self
+            .logs
+            .iter()
+            .filter_map(|log| {
+                E::decode_log(&RawLog {
+                    topics: log.topics.clone(),
+                    data: log.data.to_vec(),
+                })
+                .ok()
+            })
+            .collect();
+        let index = events.iter().position(|e| predicate(e));
+        match index {
+            Some(i) => events.into_iter().nth(i).unwrap(),
+            None if events.is_empty() => {
+                panic!("Tx should emit {} but emitted no such event", E::name())
+            }
+            None => panic!(
+                "Tx should emit {} matching the predicate but emitted {:?}",
+                E::name(),
+                events
+            ),
+        }
+    }
+}
+
+/// `GasMatcher` and `EventMatcher` for transactions not yet mined: they wait
+/// for the receipt and return it.
+#[cfg(test)]
+#[async_trait(?Send)]
+pub(crate) trait PendingMatcher: Sized {
+    async fn mined(self) -> TransactionReceipt;
+
+    async fn should_cost_at_most(self, gas: u64) -> TransactionReceipt {
+        self.mined().await.should_cost_at_most(gas)
+    }
+
+    async fn should_cost_between(self, min: u64, max: u64) -> TransactionReceipt {
+        self.mined().await.should_cost_between(min, max)
+    }
+
+    async fn should_emit<E, F>(self, predicate: F) -> E
+    where
+        E: EthEvent + Debug,
+        F: Fn(&E) -> bool,
+    {
+        self.mined().await.should_emit(predicate)
+    }
+}
+
+#[cfg(test)]
+#[async_trait(?Send)]
+impl<'a, P: JsonRpcClient> PendingMatcher for PendingTransaction<'a, P> {
+    async fn mined(self) -> TransactionReceipt {
+        self.await
+            .expect("Failed to wait for the tx")
+            .expect("Failed to get TX receipt, was the tx dropped?")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        deployments::deploy_or_reuse,
+        ethereum::{artifact_path, get_funded_deployer},
+        types::{Greeter, GreetingChangedFilter, TestErrors},
+    };
+    use ethers::abi::InvalidOutputType;
+
+    #[derive(Debug, PartialEq)]
+    struct InvalidPoint {
+        x: U256,
+        y: U256,
+    }
+
+    impl Detokenize for InvalidPoint {
+        fn from_tokens(tokens: Vec<Token>) -> Result<Self, InvalidOutputType> {
+            let (x, y) = Detokenize::from_tokens(tokens)?;
+            Ok(Self { x, y })
+        }
+    }
+
+    impl CustomError for InvalidPoint {
+        const CONTRACT: &'static str = "contracts/mocks/TestErrors.sol:TestErrors";
+        const NAME: &'static str = "InvalidPoint";
+    }
+
+    /// `InvalidPoint` with a field that disagrees with its declaration.
+    #[derive(Debug)]
+    struct WrongInvalidPoint {
+        x: Address,
+        y: U256,
+    }
+
+    impl Detokenize for WrongInvalidPoint {
+        fn from_tokens(tokens: Vec<Token>) -> Result<Self, InvalidOutputType> {
+            let (x, y) = Detokenize::from_tokens(tokens)?;
+            Ok(Self { x, y })
         }
+    }
+
+    impl CustomError for WrongInvalidPoint {
+        const CONTRACT: &'static str = InvalidPoint::CONTRACT;
+        const NAME: &'static str = InvalidPoint::NAME;
+    }
+
+    /// Whether `E` decodes the arguments of its declaration in the ABI.
+    fn matches_abi<E: CustomError>() -> bool {
+        let types = E::param_types();
+        // zero words are valid arguments of any type
+        abi::decode(&types, &vec![0u8; 32 * types.len()])
+            .ok()
+            .and_then(|tokens| E::from_tokens(tokens).ok())
+            .is_some()
+    }
+
+    #[test]
+    fn custom_error_abi() {
+        assert_eq!(InvalidPoint::signature(), "InvalidPoint(uint256,uint256)");
+        assert_eq!(
+            &InvalidPoint::selector()[..],
+            &keccak256(b"InvalidPoint(uint256,uint256)")[..4]
+        );
+        assert!(matches_abi::<InvalidPoint>());
+        assert!(!matches_abi::<WrongInvalidPoint>());
+    }
+
+    /// An error as formatted by a JSON-RPC client, with `data` as revert data.
+    fn rpc_error(data: &[u8]) -> String {
+        format!(
+            "(code: 3, message: execution reverted, data: Some(String(\"0x{}\")))",
+            hex::encode(data)
+        )
+    }
+
+    #[test]
+    fn revert_decoding() {
+        assert_eq!(&keccak256(b"Error(string)")[..4], &ERROR_SELECTOR);
+        assert_eq!(&keccak256(b"Panic(uint256)")[..4], &PANIC_SELECTOR);
+
+        let data = [
+            &ERROR_SELECTOR[..],
+            &abi::encode(&[Token::String("Pallas: invalid point".to_string())]),
+        ]
+        .concat();
+        assert_eq!(
+            Revert::from_error(&rpc_error(&data)),
+            Some(Revert::Error("Pallas: invalid point".to_string()))
+        );
 
-        let error = self.unwrap_err().to_string();
-        check_contains(&error, "reverted");
-        check_contains(&error, message);
+        let data = [
+            &PANIC_SELECTOR[..],
+            &abi::encode(&[Token::Uint(panic_code::DIVISION_BY_ZERO.into())]),
+        ]
+        .concat();
+        assert_eq!(
+            Revert::from_error(&rpc_error(&data)),
+            Some(Revert::Panic(panic_code::DIVISION_BY_ZERO.into()))
+        );
+
+        let data = [
+            &InvalidPoint::selector()[..],
+            &abi::encode(&[Token::Uint(1.into()), Token::Uint(2.into())]),
+        ]
+        .concat();
+        let revert = Revert::from_error(&rpc_error(&data)).unwrap();
+        assert_eq!(
+            revert.custom::<InvalidPoint>(),
+            Some(InvalidPoint {
+                x: 1.into(),
+                y: 2.into()
+            })
+        );
+        Some(revert).should_revert_with::<InvalidPoint>();
+
+        // addresses and hashes are not mistaken for revert data
+        let error = format!("execution reverted, from: {:?}", Address::zero());
+        assert_eq!(Revert::from_error(&error), Some(Revert::Unknown(error)));
+        assert_eq!(Revert::from_error("connection refused"), None);
+    }
+
+    #[tokio::test]
+    async fn test_revert_reasons() -> anyhow::Result<()> {
+        let client = get_funded_deployer().await?;
+        let path = artifact_path("contracts/mocks/TestErrors.sol:TestErrors")?;
+        let contract = deploy_or_reuse(client.clone(), &path, ()).await?;
+        let contract = TestErrors::new(contract.address(), client.clone());
+
+        contract
+            .divide(4.into(), 2.into())
+            .call()
+            .await
+            .should_not_revert();
+        contract
+            .revert_with_message("oops".to_string())
+            .call()
+            .await
+            .should_revert_with_message("oops");
+        contract
+            .divide(1.into(), 0.into())
+            .call()
+            .await
+            .should_revert_with_panic(panic_code::DIVISION_BY_ZERO);
+        contract
+            .add(U256::max_value(), 1.into())
+            .call()
+            .await
+            .should_revert_with_panic(panic_code::ARITHMETIC_OVERFLOW);
+        contract
+            .value_at(0.into())
+            .call()
+            .await
+            .should_revert_with_panic(panic_code::ARRAY_OUT_OF_BOUNDS);
+        let error = contract
+            .revert_with_custom_error(1.into(), 2.into())
+            .call()
+            .await
+            .should_revert_with::<InvalidPoint>();
+        assert_eq!(
+            error,
+            InvalidPoint {
+                x: 1.into(),
+                y: 2.into()
+            }
+        );
+
+        // send() fails when estimating the gas
+        contract
+            .increment_by(1.into(), 0.into())
+            .legacy()
+            .send()
+            .await
+            .should_revert_with_panic(panic_code::DIVISION_BY_ZERO);
+
+        // with a fixed gas limit the tx is mined, the revert is found by replaying it
+        let receipt = contract
+            .increment_by(1.into(), 0.into())
+            .legacy()
+            .gas(100_000)
+            .send()
+            .await?
+            .await?
+            .unwrap();
+        mined_revert(client.as_ref(), &receipt)
+            .await?
+            .should_revert_with_panic(panic_code::DIVISION_BY_ZERO);
+
+        let receipt = contract
+            .increment_by(4.into(), 2.into())
+            .legacy()
+            .send()
+            .await?
+            .await?
+            .unwrap();
+        mined_revert(client.as_ref(), &receipt)
+            .await?
+            .should_not_revert();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_gas_and_events() -> anyhow::Result<()> {
+        let client = get_funded_deployer().await?;
+        let path = artifact_path("contracts/Greeter.sol:Greeter")?;
+        let contract = deploy_or_reuse(client.clone(), &path, ("Hello".to_string(),)).await?;
+        let contract = Greeter::new(contract.address(), client.clone());
+
+        let call = contract.set_greeting("Hi!".to_string()).legacy();
+        let event: GreetingChangedFilter = call
+            .send()
+            .await?
+            .should_emit(|e: &GreetingChangedFilter| e.greeting == "Hi!")
+            .await;
+        let sender = client.inner().address();
+        assert_eq!(event.sender, sender);
+
+        let receipt = call
+            .send()
+            .await?
+            .should_cost_between(21_000, 100_000)
+            .await;
+        receipt.should_emit(|e: &GreetingChangedFilter| e.sender == sender);
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "should cost at most 999 gas but used 1000")]
+    fn gas_ceiling() {
+        GasMeasurement {
+            operation: "add".to_string(),
+            input_size: 1,
+            estimated: 1000,
+            used: 1000,
+        }
+        .should_cost_between(1000, 1000)
+        .should_cost_at_most(999);
     }
 }
@@ -0,0 +1,193 @@
+//! Conformance of arkworks (`ark-pallas`/`ark-vesta`), the zcash
+//! `pasta_curves` crate and the Solidity libraries: they must agree on the
+//! generators, the encodings and the arithmetic for proofs of both
+//! ecosystems to be verified with the same contracts.
+#![cfg(all(test, feature = "ark-0-3", feature = "pasta-curves"))]
+
+use crate::{
+    deployments::deploy_or_reuse,
+    ethereum::{artifact_path, get_funded_deployer},
+    multicall::Batch,
+    types::{
+        field_to_u256, is_y_greatest, u256_to_pasta_field, y_parity, PallasAffinePoint,
+        PallasProjectivePoint, TestPallas, TestVesta, VestaAffinePoint, VestaProjectivePoint,
+    },
+};
+use anyhow::Result;
+use ark_ec::{AffineCurve, ProjectiveCurve};
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use ark_std::{One, UniformRand, Zero};
+use ethers::prelude::*;
+use pasta_curves::{
+    group::{prime::PrimeCurveAffine, Curve, Group, GroupEncoding},
+    pallas, vesta,
+};
+use std::convert::TryFrom;
+
+macro_rules! conformance_test {
+    ($test:ident, $ark:ident, $pasta:ident, $contract:ident, $affine:ident, $projective:ident) => {
+        #[tokio::test]
+        async fn $test() -> Result<()> {
+            let rng = &mut ark_std::test_rng();
+            let client = get_funded_deployer().await?;
+            let path = artifact_path(concat!(
+                "contracts/mocks/",
+                stringify!($contract),
+                ".sol:",
+                stringify!($contract)
+            ))?;
+            let contract = deploy_or_reuse(client.clone(), &path, ()).await?;
+            let contract = $contract::new(contract.address(), client.clone());
+
+            let to_pasta = |p: $ark::Affine| $pasta::Affine::try_from($affine::from(p)).unwrap();
+            let to_pasta_scalar =
+                |s: $ark::Fr| u256_to_pasta_field::<$pasta::Scalar>(field_to_u256(s)).unwrap();
+
+            // generators
+            let generator = $affine::from($ark::Affine::prime_subgroup_generator());
+            assert_eq!(generator, $pasta::Affine::generator().into());
+            assert_eq!(
+                generator,
+                $affine::from(contract.affine_generator().call().await?)
+            );
+
+            // arithmetic
+            let mut add = Batch::new(client.clone());
+            let mut add_expected = vec![];
+            let mut mul = Batch::new(client.clone());
+            let mut mul_expected = vec![];
+            for _ in 0..20 {
+                let p1 = $ark::Projective::rand(rng);
+                let p2 = $ark::Projective::rand(rng);
+                let s = $ark::Fr::rand(rng);
+                let (q1, q2) = (to_pasta(p1.into_affine()), to_pasta(p2.into_affine()));
+
+                // both use Jacobian coordinates
+                let q1_projective = $pasta::Point::try_from($projective::from(p1)).unwrap();
+                assert_eq!(q1_projective, q1.to_curve());
+                assert_eq!($ark::Projective::from($projective::from(q1_projective)), p1);
+
+                let sum = $affine::from((p1 + p2).into_affine());
+                assert_eq!(sum, (q1 + q2).to_affine().into());
+                assert_eq!(
+                    $affine::from(p1.double().into_affine()),
+                    q1_projective.double().to_affine().into()
+                );
+                let product = p1.mul(s.into_repr());
+                let q_product = q1 * to_pasta_scalar(s);
+                assert_eq!(
+                    $ark::Projective::from($projective::from(q_product)),
+                    product
+                );
+
+                add.add(contract.affine_add(p1.into_affine().into(), p2.into_affine().into()));
+                add_expected.push(sum);
+                mul.add(contract.affine_scalar_mul(p1.into_affine().into(), field_to_u256(s)));
+                mul_expected.push($affine::from(product.into_affine()));
+            }
+            for (res, expected) in add.call_all().await?.into_iter().zip(add_expected) {
+                assert_eq!($affine::from(res), expected);
+            }
+            for (res, expected) in mul.call_all().await?.into_iter().zip(mul_expected) {
+                assert_eq!($affine::from(res), expected);
+            }
+
+            // compressed encodings: both store x in little endian, pasta_curves
+            // in 32 bytes with the parity of y in the most significant bit,
+            // arkworks in 33 bytes with its flags in the last one, `y > -y`
+            // in bit 7 and the point of infinity in bit 6
+            let mut parity = Batch::new(client.clone());
+            let mut greatest = Batch::new(client);
+            let points: Vec<$ark::Affine> = (0..20)
+                .map(|_| $ark::Projective::rand(rng).into_affine())
+                .collect();
+            for p in points.iter() {
+                let q = to_pasta(*p);
+                let pasta_bytes = q.to_bytes();
+                let mut ark_bytes = vec![];
+                p.serialize(&mut ark_bytes).unwrap();
+                assert_eq!(ark_bytes.len(), 33);
+                assert_eq!(pasta_bytes[..31], ark_bytes[..31]);
+                assert_eq!(pasta_bytes[31] & 0x7f, ark_bytes[31]);
+                assert_eq!(pasta_bytes[31] >> 7, y_parity(p.y));
+                assert_eq!(ark_bytes[32] & 0x7f, 0);
+                assert_eq!(ark_bytes[32] >> 7 == 1, is_y_greatest(p.y));
+                assert_eq!(
+                    Option::<$pasta::Affine>::from($pasta::Affine::from_bytes(&pasta_bytes)),
+                    Some(q)
+                );
+
+                parity.add(contract.y_parity((*p).into()));
+                greatest.add(contract.is_y_greatest((*p).into()));
+            }
+            let parity = parity.call_all().await?;
+            let greatest = greatest.call_all().await?;
+            for (i, p) in points.iter().enumerate() {
+                assert_eq!(parity[i], y_parity(p.y));
+                assert_eq!(greatest[i], is_y_greatest(p.y));
+            }
+
+            // point of infinity: (0, 0) in Solidity, zero bytes in
+            // pasta_curves, and a flag in bit 6 of the last byte for arkworks
+            let zero = $ark::Affine::zero();
+            assert_eq!($affine::from(zero), $pasta::Affine::identity().into());
+            assert_eq!(to_pasta(zero), $pasta::Affine::identity());
+            assert_eq!(to_pasta(zero).to_bytes(), [0u8; 32]);
+            let mut ark_bytes = vec![];
+            zero.serialize(&mut ark_bytes).unwrap();
+            assert_eq!(ark_bytes.len(), 33);
+            assert_eq!(ark_bytes[..32], [0u8; 32]);
+            assert_eq!(ark_bytes.last(), Some(&(1 << 6)));
+
+            // pasta_curves rejects what it can't represent: points off the
+            // curve, and coordinates above the modulus (which arkworks
+            // reduces)
+            let off_curve = $affine {
+                x: U256::one(),
+                y: U256::one(),
+            };
+            assert!($pasta::Affine::try_from(off_curve).is_err());
+            let modulus = field_to_u256(-$ark::Fq::one()) + U256::one();
+            let non_canonical = $affine {
+                x: generator.x + modulus,
+                y: generator.y,
+            };
+            assert!($pasta::Affine::try_from(non_canonical).is_err());
+            Ok(())
+        }
+    };
+}
+
+conformance_test!(
+    pallas_conformance,
+    ark_pallas,
+    pallas,
+    TestPallas,
+    PallasAffinePoint,
+    PallasProjectivePoint
+);
+conformance_test!(
+    vesta_conformance,
+    ark_vesta,
+    vesta,
+    TestVesta,
+    VestaAffinePoint,
+    VestaProjectivePoint
+);
+
+#[cfg(feature = "halo2")]
+#[test]
+fn halo2curves_conversions() {
+    // halo2curves re-exports the curves of pasta_curves
+    let g: halo2curves::pasta::pallas::Affine = pasta_curves::pallas::Affine::generator();
+    assert_eq!(
+        PallasAffinePoint::from(g),
+        ark_pallas::Affine::prime_subgroup_generator().into()
+    );
+    let g: halo2curves::pasta::vesta::Point = pasta_curves::vesta::Point::generator();
+    assert_eq!(
+        VestaProjectivePoint::from(g),
+        ark_vesta::Projective::prime_subgroup_generator().into()
+    );
+}
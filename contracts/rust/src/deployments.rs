@@ -0,0 +1,366 @@
+//! Registry of deployed contracts, to reuse deployments across tests and test
+//! runs instead of redeploying the same contract for every test.
+//!
+//! Deployments are keyed by chain id and by the hash of the contract bytecode,
+//! linked to its libraries, and constructor arguments. They are shared in-process and persisted to a
+//! manifest file (`DEPLOYMENT_MANIFEST`, defaults to `deployments.json` in
+//! the crate root). A deployment is only reused if the code at its address is
+//! still the code that was deployed, so restarting a dev chain is harmless.
+//!
+//! Tests that need fresh state should use `ethereum::deploy` directly; setting
+//! `FRESH_DEPLOYMENTS` disables reuse altogether.
+use crate::{
+    artifacts::Artifact,
+    ethereum::{deploy_with_config, link_unlinked_libraries, DeployConfig},
+};
+use anyhow::Result;
+use ethers::{
+    abi::Tokenize,
+    contract::Contract,
+    prelude::{artifacts::BytecodeObject, Address, Middleware, H256, U256},
+    utils::keccak256,
+};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// A contract deployed on some chain.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deployment {
+    pub address: Address,
+    /// keccak256 hash of the runtime code at `address` right after deployment
+    pub code_hash: H256,
+    /// artifact path the contract was deployed from
+    pub path: PathBuf,
+}
+
+/// chain id -> deployment key -> deployment
+type Manifest = BTreeMap<String, BTreeMap<String, Deployment>>;
+
+/// Deployment of one key, locked while deploying so that concurrent tests
+/// wait for a single deployment of each contract.
+type Slot = Arc<tokio::sync::Mutex<Option<Deployment>>>;
+
+lazy_static! {
+    /// Deployments known to this process. The map is only locked to find the
+    /// slot of a key, deployments of different keys run concurrently.
+    static ref DEPLOYMENTS: Mutex<HashMap<(U256, H256), Slot>> = Mutex::new(HashMap::new());
+
+    /// Held while updating the manifest.
+    static ref MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+}
+
+fn manifest_path() -> PathBuf {
+    match env::var("DEPLOYMENT_MANIFEST") {
+        Ok(val) => PathBuf::from(val),
+        Err(_) => Path::new(env!("CARGO_MANIFEST_DIR")).join("deployments.json"),
+    }
+}
+
+fn load_manifest(path: &Path) -> Result<Manifest> {
+    if !path.exists() {
+        return Ok(Manifest::new());
+    }
+    Ok(serde_json::from_reader(fs::File::open(path)?)?)
+}
+
+/// Record `deployment` in the manifest at `path`. The manifest is replaced
+/// atomically, so that concurrent readers never see a partial file.
+fn save_to_manifest(path: &Path, chain_id: U256, key: H256, deployment: &Deployment) -> Result<()> {
+    let _guard = MANIFEST_LOCK.lock().unwrap();
+    let mut manifest = load_manifest(path)?;
+    manifest
+        .entry(chain_id.to_string())
+        .or_default()
+        .insert(format!("{:?}", key), deployment.clone());
+    let tmp = path.with_extension(format!("tmp-{}", std::process::id()));
+    fs::write(&tmp, serde_json::to_string_pretty(&manifest)?)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Whether `deployment` is still present on chain.
+async fn is_deployed<M: Middleware>(client: &M, deployment: &Deployment) -> bool {
+    match client.get_code(deployment.address, None).await {
+        Ok(code) => !code.as_ref().is_empty() && H256(keccak256(&code)) == deployment.code_hash,
+        Err(_) => false,
+    }
+}
+
+/// Key of a deployment of `bytecode` with `constructor_args`, on any chain.
+/// `bytecode` is linked first, so that the same contract linked to other
+/// libraries gets another key.
+fn deployment_key<T: Tokenize>(bytecode: &BytecodeObject, constructor_args: T) -> Result<H256> {
+    let mut preimage = serde_json::to_vec(bytecode)?;
+    preimage.extend(ethers::abi::encode(&constructor_args.into_tokens()));
+    Ok(H256(keccak256(&preimage)))
+}
+
+/// Like `ethereum::deploy`, but reuse an existing deployment of the same
+/// contract with the same constructor arguments on the same chain.
+pub async fn deploy_or_reuse<M: 'static + Middleware, T: Tokenize + Clone>(
+    client: Arc<M>,
+    path: &Path,
+    constructor_args: T,
+) -> Result<Contract<M>> {
+    let fresh = env::var("FRESH_DEPLOYMENTS").is_ok();
+    deploy_or_reuse_with_manifest(client, path, constructor_args, &manifest_path(), fresh).await
+}
+
+/// `deploy_or_reuse` recording deployments in the manifest at
+/// `manifest_path`, or always deploying if `fresh` is set.
+async fn deploy_or_reuse_with_manifest<M: 'static + Middleware, T: Tokenize + Clone>(
+    client: Arc<M>,
+    path: &Path,
+    constructor_args: T,
+    manifest_path: &Path,
+    fresh: bool,
+) -> Result<Contract<M>> {
+    let config = DeployConfig::from_env()?;
+    if fresh {
+        return Ok(deploy_with_config(client, path, constructor_args, &config).await?);
+    }
+
+    let Artifact {
+        abi,
+        mut bytecode,
+        link_references,
+        ..
+    } = Artifact::load(path)?;
+    // the libraries are resolved as `deploy_with_config` will link them
+    link_unlinked_libraries(
+        &mut bytecode,
+        &link_references,
+        client.clone(),
+        None,
+        &config,
+    )
+    .await?;
+    let key = deployment_key(&bytecode, constructor_args.clone())?;
+    let chain_id = client.get_chainid().await?;
+
+    let slot = DEPLOYMENTS
+        .lock()
+        .unwrap()
+        .entry((chain_id, key))
+        .or_default()
+        .clone();
+    let mut slot = slot.lock().await;
+    let known = match &*slot {
+        Some(d) => Some(d.clone()),
+        None => load_manifest(manifest_path)?
+            .get(&chain_id.to_string())
+            .and_then(|m| m.get(&format!("{:?}", key)))
+            .cloned(),
+    };
+    if let Some(deployment) = known {
+        if is_deployed(client.as_ref(), &deployment).await {
+            *slot = Some(deployment.clone());
+            return Ok(Contract::new(deployment.address, abi, client));
+        }
+    }
+
+    let contract = deploy_with_config(client.clone(), path, constructor_args, &config).await?;
+    let code = client.get_code(contract.address(), None).await?;
+    let deployment = Deployment {
+        address: contract.address(),
+        code_hash: H256(keccak256(&code)),
+        path: path.to_path_buf(),
+    };
+    save_to_manifest(manifest_path, chain_id, key, &deployment)?;
+    *slot = Some(deployment);
+    Ok(contract)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ethereum::{artifact_path, get_funded_deployer};
+    use futures::future::try_join_all;
+    use std::collections::HashSet;
+
+    /// A manifest path of its own for each test.
+    fn temp_manifest() -> PathBuf {
+        env::temp_dir().join(format!("deployments-{:x}.json", rand::random::<u64>()))
+    }
+
+    /// Constructor arguments of a `Greeter` that was never deployed before.
+    fn new_greeting() -> (String,) {
+        (format!("Hello {:x}", rand::random::<u64>()),)
+    }
+
+    fn deployment(byte: u8) -> Deployment {
+        Deployment {
+            address: Address::repeat_byte(byte),
+            code_hash: H256::repeat_byte(byte),
+            path: PathBuf::from("Greeter.json"),
+        }
+    }
+
+    #[test]
+    fn manifest_round_trip() -> Result<()> {
+        let path = temp_manifest();
+        assert!(load_manifest(&path)?.is_empty());
+
+        let key = H256::repeat_byte(0x42);
+        save_to_manifest(&path, 1.into(), key, &deployment(1))?;
+        save_to_manifest(&path, 2.into(), key, &deployment(2))?;
+        let manifest = load_manifest(&path)?;
+        assert_eq!(manifest["1"][&format!("{:?}", key)], deployment(1));
+        assert_eq!(manifest["2"][&format!("{:?}", key)], deployment(2));
+
+        // a new deployment with the same key replaces the former one
+        save_to_manifest(&path, 1.into(), key, &deployment(3))?;
+        let manifest = load_manifest(&path)?;
+        assert_eq!(manifest["1"].len(), 1);
+        assert_eq!(manifest["1"][&format!("{:?}", key)], deployment(3));
+        assert_eq!(manifest["2"][&format!("{:?}", key)], deployment(2));
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn deployment_keys() -> Result<()> {
+        let greeter = Artifact::find("contracts/Greeter.sol:Greeter")?.bytecode;
+        let errors = Artifact::find("contracts/mocks/TestErrors.sol:TestErrors")?.bytecode;
+        let hello = ("Hello".to_string(),);
+        assert_eq!(
+            deployment_key(&greeter, hello.clone())?,
+            deployment_key(&greeter, hello.clone())?
+        );
+        assert_ne!(
+            deployment_key(&greeter, hello)?,
+            deployment_key(&greeter, ("Bye".to_string(),))?
+        );
+        assert_ne!(deployment_key(&greeter, ())?, deployment_key(&errors, ())?);
+
+        // the same contract linked to other libraries
+        let artifact = Artifact::find("contracts/mocks/TestLinked.sol:TestLinked")?;
+        let linked = |byte: u8| {
+            let mut bytecode = artifact.bytecode.clone();
+            for (file, names) in &artifact.link_references {
+                for name in names.keys() {
+                    bytecode
+                        .link(file, name, Address::repeat_byte(byte))
+                        .resolve();
+                }
+            }
+            bytecode
+        };
+        assert_eq!(
+            deployment_key(&linked(1), ())?,
+            deployment_key(&linked(1), ())?
+        );
+        assert_ne!(
+            deployment_key(&linked(1), ())?,
+            deployment_key(&linked(2), ())?
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_deploy_or_reuse() -> Result<()> {
+        let client = get_funded_deployer().await?;
+        let path = artifact_path("contracts/Greeter.sol:Greeter")?;
+        let manifest = temp_manifest();
+        let args = new_greeting();
+
+        let first =
+            deploy_or_reuse_with_manifest(client.clone(), &path, args.clone(), &manifest, false)
+                .await?;
+        let second =
+            deploy_or_reuse_with_manifest(client.clone(), &path, args.clone(), &manifest, false)
+                .await?;
+        assert_eq!(first.address(), second.address());
+        let recorded = &load_manifest(&manifest)?[&client.get_chainid().await?.to_string()];
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(recorded.values().next().unwrap().address, first.address());
+
+        // `FRESH_DEPLOYMENTS` deploys anyway, and doesn't record it
+        let before = load_manifest(&manifest)?;
+        let fresh =
+            deploy_or_reuse_with_manifest(client.clone(), &path, args, &manifest, true).await?;
+        assert_ne!(fresh.address(), first.address());
+        assert_eq!(load_manifest(&manifest)?, before);
+        fs::remove_file(&manifest)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_stale_deployments() -> Result<()> {
+        let client = get_funded_deployer().await?;
+        let path = artifact_path("contracts/Greeter.sol:Greeter")?;
+        let bytecode = Artifact::load(&path)?.bytecode;
+        let chain_id = client.get_chainid().await?;
+
+        // a recorded deployment without code at its address, as after a
+        // restart of the chain
+        let manifest = temp_manifest();
+        let args = new_greeting();
+        let key = deployment_key(&bytecode, args.clone())?;
+        save_to_manifest(&manifest, chain_id, key, &deployment(0x5e))?;
+        let contract =
+            deploy_or_reuse_with_manifest(client.clone(), &path, args, &manifest, false).await?;
+        assert_ne!(contract.address(), deployment(0x5e).address);
+
+        // a recorded deployment whose code changed
+        let args = new_greeting();
+        let key = deployment_key(&bytecode, args.clone())?;
+        let stale = Deployment {
+            address: contract.address(),
+            code_hash: H256::zero(),
+            path: path.clone(),
+        };
+        save_to_manifest(&manifest, chain_id, key, &stale)?;
+        assert!(!is_deployed(client.as_ref(), &stale).await);
+        let redeployed =
+            deploy_or_reuse_with_manifest(client.clone(), &path, args, &manifest, false).await?;
+        assert_ne!(redeployed.address(), stale.address);
+        assert_eq!(
+            load_manifest(&manifest)?[&chain_id.to_string()][&format!("{:?}", key)].address,
+            redeployed.address()
+        );
+        fs::remove_file(&manifest)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_deployments() -> Result<()> {
+        let client = get_funded_deployer().await?;
+        let path = artifact_path("contracts/Greeter.sol:Greeter")?;
+        let manifest = temp_manifest();
+        let args = new_greeting();
+
+        // the in-process registry deploys the contract once
+        let contracts = try_join_all((0..8).map(|_| {
+            deploy_or_reuse_with_manifest(client.clone(), &path, args.clone(), &manifest, false)
+        }))
+        .await?;
+        assert!(contracts
+            .iter()
+            .all(|contract| contract.address() == contracts[0].address()));
+        assert_eq!(
+            load_manifest(&manifest)?[&client.get_chainid().await?.to_string()].len(),
+            1
+        );
+
+        // different contracts deploy concurrently, and are all recorded
+        let contracts = try_join_all((0..4).map(|_| {
+            deploy_or_reuse_with_manifest(client.clone(), &path, new_greeting(), &manifest, false)
+        }))
+        .await?;
+        assert_eq!(
+            load_manifest(&manifest)?[&client.get_chainid().await?.to_string()].len(),
+            5
+        );
+        let addresses: HashSet<Address> = contracts.iter().map(|c| c.address()).collect();
+        assert_eq!(addresses.len(), 4);
+        fs::remove_file(&manifest)?;
+        Ok(())
+    }
+}
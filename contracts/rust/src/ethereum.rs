@@ -1,125 +1,596 @@
 #![cfg_attr(debug_assertions, allow(dead_code))]
-use anyhow::Result;
 use async_recursion::async_recursion;
 use ethers::{
-    abi::{Abi, Tokenize},
+    abi::Tokenize,
     contract::Contract,
-    core::k256::ecdsa::SigningKey,
     prelude::{
-        artifacts::BytecodeObject, coins_bip39::English, Address, ContractFactory, Http,
-        LocalWallet, Middleware, MnemonicBuilder, Provider, Signer, SignerMiddleware,
-        TransactionRequest, Wallet, U256,
+        artifacts::BytecodeObject, Address, BlockNumber, Bytes, ContractFactory,
+        Eip1559TransactionRequest, Middleware, TransactionRequest, WalletError, H256, U256,
     },
+    types::transaction::eip2718::TypedTransaction,
+    utils::keccak256,
 };
 
-use std::{convert::TryFrom, env, fs, path::Path, sync::Arc, time::Duration};
+pub use crate::accounts::EthMiddleware;
+use crate::{
+    accounts::funded_account,
+    artifacts::{self, Artifact, LinkReferences},
+};
+use lazy_static::lazy_static;
+use snafu::{Backtrace, IntoError, OptionExt, ResultExt, Snafu};
+use std::{
+    collections::HashMap,
+    env, fmt,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
-pub async fn get_funded_deployer(
-) -> Result<Arc<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>> {
-    let rpc_url = match env::var("RPC_URL") {
-        Ok(val) => val,
-        Err(_) => "http://localhost:8545".to_string(),
-    };
+/// Type erased source of an `EthereumError`, e.g. the error of a middleware.
+pub type BoxedError = Box<dyn std::error::Error + Send + Sync>;
+
+type Result<T, E = EthereumError> = std::result::Result<T, E>;
+
+/// Errors of the functions in this module and in `accounts`.
+///
+/// Every variant captures a backtrace, available through
+/// `snafu::ErrorCompat::backtrace` if `RUST_BACKTRACE` is set.
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum EthereumError {
+    #[snafu(display("Invalid value {:?} of {}: {}", value, variable, reason))]
+    InvalidConfig {
+        variable: String,
+        value: String,
+        reason: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Invalid wallet: {}", source))]
+    InvalidWallet {
+        source: WalletError,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Not a fully qualified contract name: {}", name))]
+    InvalidName { name: String, backtrace: Backtrace },
+
+    #[snafu(display("No artifact of {} in {:?}", name, searched))]
+    ArtifactNotFound {
+        name: String,
+        searched: Vec<PathBuf>,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Unable to read artifact {:?}: {}", path, source))]
+    ReadArtifact {
+        path: PathBuf,
+        source: std::io::Error,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Unable to parse artifact {:?}: {}", path, source))]
+    ParseArtifact {
+        path: PathBuf,
+        source: BoxedError,
+        backtrace: Backtrace,
+    },
 
-    let mut provider =
-        Provider::<Http>::try_from(rpc_url.clone()).expect("could not instantiate HTTP Provider");
+    #[snafu(display("Unlinked libraries in bytecode of {:?}", path))]
+    UnlinkedBytecode { path: PathBuf, backtrace: Backtrace },
 
-    let chain_id = provider.get_chainid().await.unwrap().as_u64();
+    #[snafu(display("RPC call {} failed: {}", method, source))]
+    Rpc {
+        method: String,
+        source: BoxedError,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Unsupported transaction type: {}", reason))]
+    UnsupportedTxType {
+        reason: String,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Unable to fund {:?}: {}", address, source))]
+    Funding {
+        address: Address,
+        source: BoxedError,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Deployment of {:?} failed: {}", path, source))]
+    Deployment {
+        path: PathBuf,
+        source: BoxedError,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display(
+        "Unable to publish the deterministic deployment proxy (geth only accepts its \
+         transaction with --rpc.allow-unprotected-txs): {}",
+        source
+    ))]
+    Create2Deployer {
+        source: BoxedError,
+        backtrace: Backtrace,
+    },
+
+    #[snafu(display("Deployment of {:?} reverted, no code at {:?}", path, address))]
+    DeploymentReverted {
+        path: PathBuf,
+        address: Address,
+        backtrace: Backtrace,
+    },
+}
+
+/// Box an error of a middleware (or any other error) as a `BoxedError`.
+pub(crate) fn boxed<E: std::error::Error + Send + Sync + 'static>(e: E) -> BoxedError {
+    Box::new(e)
+}
 
-    // If MNEMONIC is set, try to use it to create a wallet,
-    // otherwise create a random wallet.
-    let deployer_wallet = match env::var("MNEMONIC") {
-        Ok(val) => MnemonicBuilder::<English>::default()
-            .phrase(val.as_str())
-            .build()?,
-        Err(_) => LocalWallet::new(&mut rand::thread_rng()),
+/// Parse the env var `variable` if it is set.
+pub(crate) fn parse_env<T, E: fmt::Display>(
+    variable: &str,
+    parse: impl FnOnce(&str) -> std::result::Result<T, E>,
+) -> Result<Option<T>> {
+    match env::var(variable) {
+        Ok(value) => match parse(&value) {
+            Ok(v) => Ok(Some(v)),
+            Err(e) => InvalidConfigSnafu {
+                variable,
+                value,
+                reason: e.to_string(),
+            }
+            .fail(),
+        },
+        Err(_) => Ok(None),
     }
-    .with_chain_id(chain_id);
-
-    // Fund the deployer if we have unlocked accounts
-    let accounts = provider.get_accounts().await.unwrap();
-    if !accounts.is_empty() {
-        let tx = TransactionRequest::new()
-            .to(deployer_wallet.address())
-            .value(ethers::utils::parse_ether(U256::from(1))?)
-            .from(accounts[0]);
-
-        // Set a lower polling interval to avoid very slow tests
-        provider = provider.interval(Duration::from_millis(100u64));
-        provider.send_transaction(tx, None).await?.await?;
-        println!("Sent funding tx to deployer");
+}
+
+/// Transaction type of the deployer's transactions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxType {
+    Legacy,
+    /// requires a chain with London support
+    Eip1559,
+}
+
+/// Configuration of the funded deployer and of contract deployments.
+#[derive(Clone, Debug)]
+pub struct DeployConfig {
+    pub tx_type: TxType,
+    /// amount sent to the deployer from the first unlocked account
+    pub funding: U256,
+    /// gas limit of deployment transactions, estimated by the node if `None`
+    pub gas_limit: Option<U256>,
+    /// addresses of already deployed libraries, see `LibraryAddresses`
+    pub libraries: LibraryAddresses,
+}
+
+impl DeployConfig {
+    /// Read the configuration from the environment:
+    /// - `TX_TYPE`: `eip1559` (default) or `legacy`
+    /// - `DEPLOYER_FUNDING`: funding of the deployer in ether, defaults to 1
+    /// - `DEPLOY_GAS_LIMIT`: gas limit of deployment transactions
+    /// - `LIBRARY_ADDRESSES`: see `library_addresses_from_env`
+    pub fn from_env() -> Result<Self> {
+        let tx_type = parse_env("TX_TYPE", |val| match val.to_lowercase().as_str() {
+            "legacy" => Ok(TxType::Legacy),
+            "eip1559" => Ok(TxType::Eip1559),
+            _ => Err("use legacy or eip1559"),
+        })?;
+        let funding = parse_env("DEPLOYER_FUNDING", |val| ethers::utils::parse_ether(val))?;
+        let gas_limit = parse_env("DEPLOY_GAS_LIMIT", U256::from_dec_str)?;
+
+        Ok(Self {
+            tx_type: tx_type.unwrap_or(TxType::Eip1559),
+            funding: funding.unwrap_or(ethers::utils::WEI_IN_ETHER),
+            gas_limit,
+            libraries: library_addresses_from_env()?,
+        })
+    }
+}
+
+/// Fail with a clear error if the chain has no base fee, i.e. no London
+/// support, since EIP-1559 transactions would be rejected.
+async fn check_london_support<M: 'static + Middleware>(client: &M) -> Result<()> {
+    let block = client
+        .get_block(BlockNumber::Latest)
+        .await
+        .map_err(boxed)
+        .context(RpcSnafu {
+            method: "eth_getBlockByNumber",
+        })?
+        .ok_or_else(|| BoxedError::from("the latest block does not exist"))
+        .context(RpcSnafu {
+            method: "eth_getBlockByNumber",
+        })?;
+    if block.base_fee_per_gas.is_none() {
+        return UnsupportedTxTypeSnafu {
+            reason: "the chain does not support EIP-1559 transactions (no base fee in the \
+                     latest block, London is not activated), use TX_TYPE=legacy instead",
+        }
+        .fail();
+    }
+    Ok(())
+}
+
+/// Turn `tx` into a transaction of type `tx_type`, estimating the EIP-1559
+/// fees if needed, and set its gas limit.
+pub(crate) async fn prepare_tx<M: 'static + Middleware>(
+    client: &M,
+    tx: &mut TypedTransaction,
+    tx_type: TxType,
+    gas_limit: Option<U256>,
+) -> Result<()> {
+    match tx_type {
+        TxType::Legacy => {
+            if let TypedTransaction::Eip1559(inner) = tx {
+                *tx = TypedTransaction::Legacy(inner.clone().into());
+            }
+        }
+        TxType::Eip1559 => {
+            check_london_support(client).await?;
+            let (max_fee, max_priority_fee) = client
+                .estimate_eip1559_fees(None)
+                .await
+                .map_err(boxed)
+                .context(RpcSnafu {
+                    method: "eth_feeHistory",
+                })?;
+            let mut inner: Eip1559TransactionRequest = match tx {
+                TypedTransaction::Legacy(inner) => Eip1559TransactionRequest {
+                    from: inner.from,
+                    to: inner.to.clone(),
+                    gas: inner.gas,
+                    value: inner.value,
+                    data: inner.data.clone(),
+                    nonce: inner.nonce,
+                    ..Default::default()
+                },
+                TypedTransaction::Eip1559(inner) => inner.clone(),
+                TypedTransaction::Eip2930(_) => {
+                    return UnsupportedTxTypeSnafu {
+                        reason: "EIP-2930 transactions are not supported",
+                    }
+                    .fail()
+                }
+            };
+            inner.max_fee_per_gas = Some(max_fee);
+            inner.max_priority_fee_per_gas = Some(max_priority_fee);
+            *tx = TypedTransaction::Eip1559(inner);
+        }
+    }
+    if let Some(gas) = gas_limit {
+        tx.set_gas(gas);
     }
+    Ok(())
+}
 
-    Ok(Arc::new(SignerMiddleware::new(provider, deployer_wallet)))
+/// A new funded account from the account pool, see `accounts`.
+pub async fn get_funded_deployer() -> Result<Arc<EthMiddleware>> {
+    get_funded_deployer_with_config(&DeployConfig::from_env()?).await
 }
 
-async fn load_contract(path: &Path) -> Result<(Abi, BytecodeObject)> {
-    let abi_path = path.join("abi.json");
-    let bin_path = path.join("bin.txt");
+pub async fn get_funded_deployer_with_config(config: &DeployConfig) -> Result<Arc<EthMiddleware>> {
+    funded_account(config).await
+}
 
-    let abi = ethers::abi::Contract::load(match fs::File::open(&abi_path) {
-        Ok(v) => v,
-        Err(_) => panic!("Unable to open path {:?}", abi_path),
-    })?;
+/// Fully qualified library names (`contracts/libraries/Foo.sol:Foo`) mapped to
+/// the addresses of already deployed instances.
+pub type LibraryAddresses = HashMap<String, Address>;
 
-    let bytecode_str = match fs::read_to_string(&bin_path) {
-        Ok(v) => v,
-        Err(_) => panic!("Unable to read from path {:?}", bin_path),
-    };
-    let trimmed = bytecode_str.trim().trim_start_matches("0x");
-    let bytecode: BytecodeObject = serde_json::from_value(serde_json::json!(trimmed)).unwrap();
+lazy_static! {
+    /// Libraries deployed by this process, keyed by chain id and fully
+    /// qualified name.
+    static ref DEPLOYED_LIBRARIES: Mutex<HashMap<(U256, String), Address>> =
+        Mutex::new(HashMap::new());
+
+    /// Held while publishing the deterministic deployment proxy.
+    static ref CREATE2_DEPLOYER_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::new(());
+}
+
+/// Path of the Hardhat or Foundry artifact of a contract given its fully
+/// qualified name, e.g. `contracts/libraries/Pallas.sol:Pallas`, see
+/// `artifacts::find`.
+pub fn artifact_path(fully_qualified_name: &str) -> Result<PathBuf> {
+    artifacts::find(fully_qualified_name)
+}
 
-    Ok((abi, bytecode))
+/// Read library addresses from the `LIBRARY_ADDRESSES` env var, a JSON object
+/// mapping fully qualified library names to addresses.
+pub fn library_addresses_from_env() -> Result<LibraryAddresses> {
+    Ok(parse_env("LIBRARY_ADDRESSES", |val| serde_json::from_str(val))?.unwrap_or_default())
 }
 
-async fn link_unlinked_libraries<M: 'static + Middleware>(
+/// Address of the library `fully_qualified_name`: taken from
+/// `config.libraries` if present, otherwise deployed (together with its own
+/// libraries) once per chain and cached, or with CREATE2 if `salt` is given.
+async fn library_address<M: 'static + Middleware>(
+    client: Arc<M>,
+    fully_qualified_name: &str,
+    salt: Option<H256>,
+    config: &DeployConfig,
+) -> Result<Address> {
+    if let Some(address) = config.libraries.get(fully_qualified_name) {
+        return Ok(*address);
+    }
+    if let Some(salt) = salt {
+        // CREATE2 deployments are idempotent, no need to cache them
+        let path = artifact_path(fully_qualified_name)?;
+        return Ok(deploy_create2(client, &path, (), salt, config)
+            .await?
+            .address());
+    }
+
+    let chain_id = client
+        .get_chainid()
+        .await
+        .map_err(boxed)
+        .context(RpcSnafu {
+            method: "eth_chainId",
+        })?;
+    let key = (chain_id, fully_qualified_name.to_string());
+    if let Some(address) = DEPLOYED_LIBRARIES.lock().unwrap().get(&key).copied() {
+        return Ok(address);
+    }
+
+    let address = deploy_with_config(client, &artifact_path(fully_qualified_name)?, (), config)
+        .await?
+        .address();
+    DEPLOYED_LIBRARIES.lock().unwrap().insert(key, address);
+    Ok(address)
+}
+
+/// Link every library referenced by `bytecode`, see `library_address`.
+pub(crate) async fn link_unlinked_libraries<M: 'static + Middleware>(
     bytecode: &mut BytecodeObject,
+    link_references: &LinkReferences,
     client: Arc<M>,
+    salt: Option<H256>,
+    config: &DeployConfig,
 ) -> Result<()> {
-    if bytecode.contains_fully_qualified_placeholder("contracts/libraries/RescueLib.sol:RescueLib")
-    {
-        // Connect to linked library if env var with address is set
-        // otherwise, deploy the library.
-        let lib_address = match env::var("RESCUE_LIB_ADDRESS") {
-            Ok(val) => val.parse::<Address>()?,
-            Err(_) => deploy(
-                client.clone(),
-                Path::new("../abi/contracts/libraries/RescueLib.sol/RescueLib"),
-                (),
-            )
-            .await?
-            .address(),
-        };
-        bytecode
-            .link(
-                "contracts/libraries/RescueLib.sol",
-                "RescueLib",
-                lib_address,
-            )
-            .resolve();
-    };
+    for (file, names) in link_references {
+        for name in names.keys() {
+            let fully_qualified_name = format!("{}:{}", file, name);
+            let address =
+                library_address(client.clone(), &fully_qualified_name, salt, config).await?;
+            bytecode.link(file, name, address).resolve();
+        }
+    }
 
     Ok(())
 }
 
-// TODO: why do we need 'static ?
-// https://docs.rs/anyhow/1.0.44/anyhow/struct.Error.html ?
-#[async_recursion(?Send)]
+/// Deploy the contract at `path` with the configuration from the environment,
+/// see `DeployConfig::from_env`.
 pub async fn deploy<M: 'static + Middleware, T: Tokenize>(
     client: Arc<M>,
     path: &Path,
     constructor_args: T,
 ) -> Result<Contract<M>> {
-    let (abi, mut bytecode) = load_contract(path).await?;
+    deploy_with_config(client, path, constructor_args, &DeployConfig::from_env()?).await
+}
+
+/// Deploy the contract at `path`, linking the given `libraries` and deploying
+/// any other library it references.
+pub async fn deploy_with_libraries<M: 'static + Middleware, T: Tokenize>(
+    client: Arc<M>,
+    path: &Path,
+    constructor_args: T,
+    libraries: &LibraryAddresses,
+) -> Result<Contract<M>> {
+    let mut config = DeployConfig::from_env()?;
+    config.libraries = libraries.clone();
+    deploy_with_config(client, path, constructor_args, &config).await
+}
+
+/// Deploy the contract at `path`. Every library it references is taken from
+/// `config.libraries` or, if missing, deployed first in dependency order.
+#[async_recursion(?Send)]
+pub async fn deploy_with_config<M: 'static + Middleware, T: Tokenize>(
+    client: Arc<M>,
+    path: &Path,
+    constructor_args: T,
+    config: &DeployConfig,
+) -> Result<Contract<M>> {
+    let Artifact {
+        abi,
+        mut bytecode,
+        link_references,
+        ..
+    } = Artifact::load(path)?;
 
     // TODO remove client clones, pass reference instead?
-    link_unlinked_libraries(&mut bytecode, client.clone()).await?;
+    link_unlinked_libraries(
+        &mut bytecode,
+        &link_references,
+        client.clone(),
+        None,
+        config,
+    )
+    .await?;
+    let bytecode = bytecode
+        .into_bytes()
+        .context(UnlinkedBytecodeSnafu { path })?;
 
-    let factory = ContractFactory::new(abi.clone(), bytecode.into_bytes().unwrap(), client.clone());
-    let contract = factory
-        .deploy(constructor_args)?
-        .legacy() // XXX This is required!
+    let factory = ContractFactory::new(abi.clone(), bytecode, client.clone());
+    let mut deployer = factory
+        .deploy(constructor_args)
+        .map_err(boxed)
+        .context(DeploymentSnafu { path })?;
+    prepare_tx(
+        client.as_ref(),
+        &mut deployer.tx,
+        config.tx_type,
+        config.gas_limit,
+    )
+    .await?;
+    let contract = deployer
         .send()
-        .await?;
+        .await
+        .map_err(boxed)
+        .context(DeploymentSnafu { path })?;
+
+    // a reverted deployment still yields a contract address, but no code
+    if !has_code(client.as_ref(), contract.address()).await? {
+        return DeploymentRevertedSnafu {
+            path,
+            address: contract.address(),
+        }
+        .fail();
+    }
     Ok(contract)
 }
+
+/// Whether there is code at `address`.
+async fn has_code<M: 'static + Middleware>(client: &M, address: Address) -> Result<bool> {
+    let code = client
+        .get_code(address, None)
+        .await
+        .map_err(boxed)
+        .context(RpcSnafu {
+            method: "eth_getCode",
+        })?;
+    Ok(!code.as_ref().is_empty())
+}
+
+/// Keyless signer of `CREATE2_DEPLOYER_TX`.
+const CREATE2_DEPLOYER_SIGNER: &str = "0x3fab184622dc19b6109349b94811493bf2a45362";
+
+/// Pre-signed transaction publishing the deterministic deployment proxy. It
+/// has no chain id (EIP-155), so it is valid on every chain.
+const CREATE2_DEPLOYER_TX: &str = "f8a58085174876e800830186a08080b853604580600e600039806000f350fe\
+    7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe03601600081602082378035\
+    828234f58015156039578182fd5b8082525050506014600cf31ba02222222222222222222222222222222222\
+    222222222222222222222222222222a022222222222222222222222222222222222222222222222222222222\
+    22222222";
+
+/// Cost of `CREATE2_DEPLOYER_TX`: gas price of 100 gwei times gas limit of
+/// 100000.
+const CREATE2_DEPLOYER_TX_COST: u64 = 10_000_000_000_000_000;
+
+/// Address of the deterministic deployment proxy
+/// (<https://github.com/Arachnid/deterministic-deployment-proxy>), which is
+/// at this address on every chain where it was published. It deploys its
+/// calldata after the first 32 bytes with CREATE2, using these 32 bytes as
+/// salt.
+pub fn create2_deployer() -> Address {
+    "0x4e59b44847b379578588920ca78fbf26c0b4956c"
+        .parse()
+        .unwrap()
+}
+
+/// Address of the contract created by `deployer` with CREATE2 (EIP-1014).
+pub fn create2_address(deployer: Address, salt: H256, init_code: &[u8]) -> Address {
+    let mut preimage = vec![0xff];
+    preimage.extend_from_slice(deployer.as_bytes());
+    preimage.extend_from_slice(salt.as_bytes());
+    preimage.extend_from_slice(&keccak256(init_code));
+    Address::from_slice(&keccak256(&preimage)[12..])
+}
+
+/// Publish the deterministic deployment proxy if it is not on chain yet, as
+/// on fresh dev nodes. The client pays for the pre-signed transaction.
+pub async fn ensure_create2_deployer<M: 'static + Middleware>(
+    client: &M,
+    config: &DeployConfig,
+) -> Result<()> {
+    let _lock = CREATE2_DEPLOYER_LOCK.lock().await;
+    if has_code(client, create2_deployer()).await? {
+        return Ok(());
+    }
+
+    let published: std::result::Result<(), BoxedError> = async {
+        let mut funding: TypedTransaction = TransactionRequest::new()
+            .to(CREATE2_DEPLOYER_SIGNER.parse::<Address>()?)
+            .value(CREATE2_DEPLOYER_TX_COST)
+            .into();
+        prepare_tx(client, &mut funding, config.tx_type, None).await?;
+        client.send_transaction(funding, None).await?.await?;
+
+        let raw_tx = hex::decode(CREATE2_DEPLOYER_TX)?;
+        client
+            .send_raw_transaction(Bytes::from(raw_tx))
+            .await?
+            .await?;
+        Ok::<(), BoxedError>(())
+    }
+    .await;
+
+    // another process may have published it in the meantime
+    if has_code(client, create2_deployer()).await? {
+        return Ok(());
+    }
+    let reason = match published {
+        Ok(()) => BoxedError::from("no code after publishing"),
+        Err(e) => e,
+    };
+    Err(Create2DeployerSnafu.into_error(reason))
+}
+
+/// Deploy the contract at `path` with CREATE2 through the deterministic
+/// deployment proxy, so that it has the same address on every chain for the
+/// same `salt`, bytecode and constructor arguments. Libraries it references
+/// are deployed the same way with the same `salt`, unless given in
+/// `config.libraries`.
+///
+/// If the contract already exists at that address, it is reused.
+#[async_recursion(?Send)]
+pub async fn deploy_create2<M: 'static + Middleware, T: Tokenize>(
+    client: Arc<M>,
+    path: &Path,
+    constructor_args: T,
+    salt: H256,
+    config: &DeployConfig,
+) -> Result<Contract<M>> {
+    let Artifact {
+        abi,
+        mut bytecode,
+        link_references,
+        ..
+    } = Artifact::load(path)?;
+    link_unlinked_libraries(
+        &mut bytecode,
+        &link_references,
+        client.clone(),
+        Some(salt),
+        config,
+    )
+    .await?;
+    let mut init_code = bytecode
+        .into_bytes()
+        .context(UnlinkedBytecodeSnafu { path })?
+        .as_ref()
+        .to_vec();
+    init_code.extend(ethers::abi::encode(&constructor_args.into_tokens()));
+
+    let address = create2_address(create2_deployer(), salt, &init_code);
+    if has_code(client.as_ref(), address).await? {
+        return Ok(Contract::new(address, abi, client));
+    }
+
+    ensure_create2_deployer(client.as_ref(), config).await?;
+    let mut data = salt.as_bytes().to_vec();
+    data.extend(init_code);
+    let mut tx: TypedTransaction = TransactionRequest::new()
+        .to(create2_deployer())
+        .data(data)
+        .into();
+    prepare_tx(client.as_ref(), &mut tx, config.tx_type, config.gas_limit).await?;
+    client
+        .send_transaction(tx, None)
+        .await
+        .map_err(boxed)
+        .context(DeploymentSnafu { path })?
+        .await
+        .map_err(boxed)
+        .context(DeploymentSnafu { path })?;
+
+    // the proxy reverts if the contract exists already, which is fine if it
+    // was deployed concurrently
+    if !has_code(client.as_ref(), address).await? {
+        return DeploymentRevertedSnafu { path, address }.fail();
+    }
+    Ok(Contract::new(address, abi, client))
+}
@@ -0,0 +1,278 @@
+//! Structured gas measurements with baseline comparison.
+//!
+//! Every measurement is keyed by an operation name and an input size, e.g.
+//! `("TestPallas.multiScalarMul", 4)`, and records both the `estimate_gas`
+//! result and the `gas_used` of the mined receipt.
+//!
+//! Environment variables:
+//! - `GAS_REPORT`: if set, the report is (re)written to this path as JSON
+//!   after each measurement, merged with whatever the file already contains.
+//! - `GAS_BASELINE`: path of the baseline report, defaults to
+//!   `gas-baseline.json` in the crate root. Comparison is skipped if the file
+//!   does not exist, except in CI (`CI` is set), where it is an error.
+//! - `GAS_TOLERANCE`: allowed increase over the baseline in percent,
+//!   defaults to `DEFAULT_TOLERANCE`.
+//!
+//! Measured transactions are of the type given by `TX_TYPE`, see
+//! `ethereum::DeployConfig`.
+use crate::ethereum::{DeployConfig, TxType};
+use anyhow::{anyhow, Result};
+use ethers::{abi::Detokenize, contract::builders::ContractCall, providers::Middleware};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Default allowed increase over the baseline, in percent.
+pub const DEFAULT_TOLERANCE: f64 = 2.0;
+
+lazy_static! {
+    static ref REPORT: Mutex<GasReport> = Mutex::new(GasReport::default());
+    static ref BASELINE: Option<GasReport> = load_baseline();
+}
+
+/// Gas consumed by one operation on one input size.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GasMeasurement {
+    pub operation: String,
+    pub input_size: usize,
+    /// result of `estimate_gas`
+    pub estimated: u64,
+    /// `gas_used` of the mined transaction receipt
+    pub used: u64,
+}
+
+impl GasMeasurement {
+    /// key of the measurement in a `GasReport`
+    pub fn key(&self) -> String {
+        format!("{}/{}", self.operation, self.input_size)
+    }
+}
+
+/// A measurement that exceeds its baseline by more than the tolerance.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GasRegression {
+    pub baseline: GasMeasurement,
+    pub measured: GasMeasurement,
+}
+
+impl fmt::Display for GasRegression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "gas regression for {}: estimated {} -> {}, used {} -> {}",
+            self.measured.key(),
+            self.baseline.estimated,
+            self.measured.estimated,
+            self.baseline.used,
+            self.measured.used
+        )
+    }
+}
+
+/// A collection of gas measurements, serialized as a JSON object keyed by
+/// `operation/input_size`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GasReport {
+    pub measurements: BTreeMap<String, GasMeasurement>,
+}
+
+impl GasReport {
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_reader(fs::File::open(path)?)?)
+    }
+
+    /// Write the report to `path`, keeping entries of an existing report at
+    /// that path which are not overwritten by this one.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut merged = if path.exists() {
+            Self::load(path)?
+        } else {
+            Self::default()
+        };
+        merged.measurements.extend(self.measurements.clone());
+        fs::write(path, serde_json::to_string_pretty(&merged)?)?;
+        Ok(())
+    }
+
+    pub fn insert(&mut self, measurement: GasMeasurement) {
+        self.measurements.insert(measurement.key(), measurement);
+    }
+
+    pub fn get(&self, operation: &str, input_size: usize) -> Option<&GasMeasurement> {
+        self.measurements
+            .get(&format!("{}/{}", operation, input_size))
+    }
+
+    /// Check `measurement` against this (baseline) report. Operations missing
+    /// from the baseline are never regressions.
+    pub fn check(&self, measurement: &GasMeasurement, tolerance: f64) -> Option<GasRegression> {
+        let baseline = self.measurements.get(&measurement.key())?;
+        let exceeds = |base: u64, new: u64| new as f64 > base as f64 * (1.0 + tolerance / 100.0);
+        if exceeds(baseline.estimated, measurement.estimated)
+            || exceeds(baseline.used, measurement.used)
+        {
+            Some(GasRegression {
+                baseline: baseline.clone(),
+                measured: measurement.clone(),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// All measurements of `self` that regress with respect to `baseline`.
+    pub fn regressions(&self, baseline: &GasReport, tolerance: f64) -> Vec<GasRegression> {
+        self.measurements
+            .values()
+            .filter_map(|m| baseline.check(m, tolerance))
+            .collect()
+    }
+}
+
+fn baseline_path() -> PathBuf {
+    match env::var("GAS_BASELINE") {
+        Ok(val) => PathBuf::from(val),
+        Err(_) => Path::new(env!("CARGO_MANIFEST_DIR")).join("gas-baseline.json"),
+    }
+}
+
+fn load_baseline() -> Option<GasReport> {
+    let path = baseline_path();
+    if !path.exists() {
+        // in CI, a missing baseline would silently disable the regression checks
+        if env::var("CI").is_ok() {
+            panic!(
+                "No gas baseline at {:?}, generate it with GAS_REPORT=gas-baseline.json",
+                path
+            );
+        }
+        return None;
+    }
+    match GasReport::load(&path) {
+        Ok(report) => Some(report),
+        Err(e) => panic!("Unable to parse gas baseline {:?}: {}", path, e),
+    }
+}
+
+fn tolerance() -> f64 {
+    match env::var("GAS_TOLERANCE") {
+        Ok(val) => val
+            .parse()
+            .unwrap_or_else(|_| panic!("GAS_TOLERANCE is not a number: {}", val)),
+        Err(_) => DEFAULT_TOLERANCE,
+    }
+}
+
+/// Add `measurement` to the global report, write the report if `GAS_REPORT`
+/// is set, and fail if the measurement regresses against the baseline.
+pub fn record(measurement: GasMeasurement) -> Result<GasMeasurement> {
+    {
+        let mut report = REPORT.lock().unwrap();
+        report.insert(measurement.clone());
+        if let Ok(path) = env::var("GAS_REPORT") {
+            report.save(Path::new(&path))?;
+        }
+    }
+
+    if let Some(baseline) = BASELINE.as_ref() {
+        if let Some(regression) = baseline.check(&measurement, tolerance()) {
+            return Err(anyhow!("{}", regression));
+        }
+    }
+    Ok(measurement)
+}
+
+/// Estimate and execute `call` as a transaction of the configured type, then
+/// record its gas cost under `operation` and `input_size`.
+pub async fn measure<M, D>(
+    operation: &str,
+    input_size: usize,
+    call: ContractCall<M, D>,
+) -> Result<GasMeasurement>
+where
+    M: 'static + Middleware,
+    D: Detokenize,
+{
+    let call = match DeployConfig::from_env()?.tx_type {
+        TxType::Legacy => call.legacy(),
+        TxType::Eip1559 => call,
+    };
+    let estimated = call.estimate_gas().await?.as_u64();
+    let receipt = call
+        .send()
+        .await?
+        .await?
+        .ok_or_else(|| anyhow!("Failed to get TX receipt for {}", operation))?;
+    let used = receipt
+        .gas_used
+        .ok_or_else(|| anyhow!("No gas_used in TX receipt for {}", operation))?
+        .as_u64();
+
+    record(GasMeasurement {
+        operation: operation.to_string(),
+        input_size,
+        estimated,
+        used,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn measurement(operation: &str, input_size: usize, gas: u64) -> GasMeasurement {
+        GasMeasurement {
+            operation: operation.to_string(),
+            input_size,
+            estimated: gas,
+            used: gas,
+        }
+    }
+
+    #[test]
+    fn regression_detection() {
+        let mut baseline = GasReport::default();
+        baseline.insert(measurement("add", 1, 1000));
+        baseline.insert(measurement("msm", 2, 2000));
+
+        // within tolerance
+        assert_eq!(baseline.check(&measurement("add", 1, 1000), 0.0), None);
+        assert_eq!(baseline.check(&measurement("add", 1, 900), 0.0), None);
+        assert_eq!(baseline.check(&measurement("add", 1, 1020), 2.0), None);
+        // unknown operation or input size
+        assert_eq!(baseline.check(&measurement("double", 1, 5000), 0.0), None);
+        assert_eq!(baseline.check(&measurement("msm", 3, 5000), 0.0), None);
+
+        // regressions
+        assert!(baseline.check(&measurement("add", 1, 1001), 0.0).is_some());
+        assert!(baseline.check(&measurement("add", 1, 1021), 2.0).is_some());
+        let mut m = measurement("msm", 2, 2000);
+        m.used = 2500;
+        assert!(baseline.check(&m, 10.0).is_some());
+
+        let mut report = GasReport::default();
+        report.insert(measurement("add", 1, 1100));
+        report.insert(measurement("msm", 2, 1900));
+        let regressions = report.regressions(&baseline, 5.0);
+        assert_eq!(regressions.len(), 1);
+        assert_eq!(regressions[0].measured.key(), "add/1");
+    }
+
+    #[test]
+    fn report_serialization() {
+        let mut report = GasReport::default();
+        report.insert(measurement("add", 1, 1000));
+        report.insert(measurement("msm", 4, 4000));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["msm/4"]["estimated"], 4000);
+        assert_eq!(serde_json::from_value::<GasReport>(json).unwrap(), report);
+        assert_eq!(report.get("add", 1), Some(&measurement("add", 1, 1000)));
+    }
+}
@@ -0,0 +1,242 @@
+//! Reference implementation of `HashToField.sol`: hash_to_field of the IETF
+//! hash-to-curve draft
+//! (<https://datatracker.ietf.org/doc/html/draft-irtf-cfrg-hash-to-curve>),
+//! section 5, with expand_message_xmd over Keccak-256 or SHA-256.
+//!
+//! Each field element is reduced from `L = 64` uniform bytes, a 512-bit wide
+//! reduction, rather than the minimal 48 bytes of the draft for a 255-bit
+//! field and k = 128.
+use ark_ff::PrimeField;
+use ethers::utils::keccak256;
+use sha2::{Digest, Sha256};
+
+/// Bytes of uniform output per field element.
+pub const L: usize = 64;
+
+/// Hash function of expand_message_xmd.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XmdHash {
+    Keccak256,
+    Sha256,
+}
+
+impl XmdHash {
+    /// Input block size `s_in_bytes`, the rate for Keccak-256.
+    pub fn block_size(self) -> usize {
+        match self {
+            XmdHash::Keccak256 => 136,
+            XmdHash::Sha256 => 64,
+        }
+    }
+
+    pub fn hash(self, data: &[u8]) -> [u8; 32] {
+        match self {
+            XmdHash::Keccak256 => keccak256(data),
+            XmdHash::Sha256 => {
+                let mut output = [0u8; 32];
+                output.copy_from_slice(&Sha256::digest(data));
+                output
+            }
+        }
+    }
+}
+
+/// expand_message_xmd, section 5.3.1 of the draft.
+///
+/// # Panics
+///
+/// If `len_in_bytes` is above `255 * 32` or 65535, or `dst` is empty (which
+/// the draft forbids) or longer than 255 bytes.
+pub fn expand_message_xmd(hash: XmdHash, msg: &[u8], dst: &[u8], len_in_bytes: usize) -> Vec<u8> {
+    let ell = (len_in_bytes + 31) / 32;
+    assert!(ell <= 255 && len_in_bytes <= 65535 && dst.len() <= 255);
+    assert!(!dst.is_empty(), "empty domain separation tag");
+
+    let dst_prime = [dst, &[dst.len() as u8][..]].concat();
+    let msg_prime = [
+        &vec![0u8; hash.block_size()][..],
+        msg,
+        &(len_in_bytes as u16).to_be_bytes()[..],
+        &[0u8][..],
+        &dst_prime[..],
+    ]
+    .concat();
+    let b_0 = hash.hash(&msg_prime);
+
+    let mut uniform_bytes = Vec::with_capacity(ell * 32);
+    let mut b_i = hash.hash(&[&b_0[..], &[1u8][..], &dst_prime[..]].concat());
+    uniform_bytes.extend_from_slice(&b_i);
+    for i in 2..=ell {
+        let xor: Vec<u8> = b_0.iter().zip(b_i.iter()).map(|(a, b)| a ^ b).collect();
+        b_i = hash.hash(&[&xor[..], &[i as u8][..], &dst_prime[..]].concat());
+        uniform_bytes.extend_from_slice(&b_i);
+    }
+    uniform_bytes.truncate(len_in_bytes);
+    uniform_bytes
+}
+
+/// hash_to_field, section 5.2 of the draft: `count` elements of `F`, each
+/// reduced from `L` bytes of `expand_message_xmd(msg, dst)`.
+pub fn hash_to_field<F: PrimeField>(hash: XmdHash, msg: &[u8], dst: &[u8], count: usize) -> Vec<F> {
+    expand_message_xmd(hash, msg, dst, count * L)
+        .chunks(L)
+        .map(F::from_be_bytes_mod_order)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        assertion::Matcher,
+        deployments::deploy_or_reuse,
+        ethereum::{artifact_path, get_funded_deployer},
+        multicall::Batch,
+        types::{field_to_u256, TestHashToField},
+    };
+    use anyhow::Result;
+    use ark_std::One;
+    use ethers::prelude::*;
+    use rand::{Rng, RngCore};
+
+    // Appendix K.1 of the draft (expand_message_xmd with SHA-256)
+    #[test]
+    fn sha256_test_vectors() {
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+        let vectors: [(&[u8], usize, &str); 3] = [
+            (
+                b"",
+                0x20,
+                "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235",
+            ),
+            (
+                b"abc",
+                0x20,
+                "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615",
+            ),
+            (
+                b"",
+                0x80,
+                "af84c27ccfd45d41914fdff5df25293e221afc53d8ad2ac06d5e3e29485dadbe\
+                 e0d121587713a3e0dd4d5e69e93eb7cd4f5df4cd103e188cf60cb02edc3edf18\
+                 eda8576c412b18ffb658e3dd6ec849469b979d444cf7b26911a08e63cf31f9dc\
+                 c541708d3491184472c2c29bb749d4286b004ceb5ee6b9a7fa5b646c993f0ced",
+            ),
+        ];
+        for (msg, len, expected) in vectors {
+            assert_eq!(
+                hex::encode(expand_message_xmd(XmdHash::Sha256, msg, dst, len)),
+                expected
+            );
+        }
+    }
+
+    // computed independently, from the 64-byte chunks of
+    // expand_message_xmd(SHA-256, "abc", DST of K.1, 128) as big-endian
+    // integers (all above the moduli) reduced modulo p and r
+    #[test]
+    fn wide_reduction() {
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+        let to_dec = |elements: Vec<U256>| -> Vec<String> {
+            elements.into_iter().map(|e| e.to_string()).collect()
+        };
+        let base = hash_to_field::<ark_pallas::Fq>(XmdHash::Sha256, b"abc", dst, 2);
+        assert_eq!(
+            to_dec(base.into_iter().map(field_to_u256).collect()),
+            [
+                "4864677638503957184580322462191025687212008158258074383641640515897872789480",
+                "26749562708739704209248629897961139050514862580744013354012879028768766253431",
+            ]
+        );
+        let scalar = hash_to_field::<ark_pallas::Fr>(XmdHash::Sha256, b"abc", dst, 2);
+        assert_eq!(
+            to_dec(scalar.into_iter().map(field_to_u256).collect()),
+            [
+                "5777269132755732137262936753541236171584839615083758289632824523676330733071",
+                "22992354768397379929806666587891708231723991631893196702572116985776948117281",
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "empty domain separation tag")]
+    fn empty_dst() {
+        expand_message_xmd(XmdHash::Sha256, b"abc", b"", 32);
+    }
+
+    #[tokio::test]
+    async fn test_hash_to_field() -> Result<()> {
+        let rng = &mut ark_std::test_rng();
+        let client = get_funded_deployer().await?;
+        let path = artifact_path("contracts/mocks/TestHashToField.sol:TestHashToField")?;
+        let contract = deploy_or_reuse(client.clone(), &path, ()).await?;
+        let contract = TestHashToField::new(contract.address(), client.clone());
+
+        // Pallas' base field is Vesta's scalar field and vice versa
+        let p_mod = field_to_u256(-ark_pallas::Fq::one()) + U256::one();
+        let r_mod = field_to_u256(-ark_pallas::Fr::one()) + U256::one();
+
+        // the draft forbids empty tags
+        contract
+            .expand_message_xmd(Bytes::default(), Bytes::default(), 32.into(), false)
+            .call()
+            .await
+            .should_revert_with_message("HashToField: empty dst");
+
+        let mut cases = vec![(vec![], b"DST".to_vec()), (b"abc".to_vec(), b"D".to_vec())];
+        for _ in 0..20 {
+            let mut msg = vec![0u8; rng.gen_range(0..300)];
+            rng.fill_bytes(&mut msg);
+            let mut dst = vec![0u8; rng.gen_range(1..=255)];
+            rng.fill_bytes(&mut dst);
+            cases.push((msg, dst));
+        }
+
+        let mut expand = Batch::new(client.clone());
+        let mut expand_sha256 = Batch::new(client.clone());
+        let mut base = Batch::new(client.clone());
+        let mut scalar = Batch::new(client);
+        for (i, (msg, dst)) in cases.iter().enumerate() {
+            let len = U256::from(1 + i * 37);
+            let count = U256::from(1 + i % 4);
+            let (msg, dst): (Bytes, Bytes) = (msg.clone().into(), dst.clone().into());
+            expand.add(contract.expand_message_xmd(msg.clone(), dst.clone(), len, false));
+            expand_sha256.add(contract.expand_message_xmd(msg.clone(), dst.clone(), len, true));
+            base.add(contract.hash_to_field(msg.clone(), dst.clone(), count, p_mod, false));
+            scalar.add(contract.hash_to_field(msg, dst, count, r_mod, true));
+        }
+        let expand = expand.call_all().await?;
+        let expand_sha256 = expand_sha256.call_all().await?;
+        let base = base.call_all().await?;
+        let scalar = scalar.call_all().await?;
+
+        for (i, (msg, dst)) in cases.iter().enumerate() {
+            let (len, count) = (1 + i * 37, 1 + i % 4);
+            assert_eq!(
+                expand[i].to_vec(),
+                expand_message_xmd(XmdHash::Keccak256, msg, dst, len),
+                "case {}",
+                i
+            );
+            assert_eq!(
+                expand_sha256[i].to_vec(),
+                expand_message_xmd(XmdHash::Sha256, msg, dst, len),
+                "case {}",
+                i
+            );
+            let expected: Vec<U256> =
+                hash_to_field::<ark_pallas::Fq>(XmdHash::Keccak256, msg, dst, count)
+                    .into_iter()
+                    .map(field_to_u256)
+                    .collect();
+            assert_eq!(base[i], expected, "case {}", i);
+            let expected: Vec<U256> =
+                hash_to_field::<ark_pallas::Fr>(XmdHash::Sha256, msg, dst, count)
+                    .into_iter()
+                    .map(field_to_u256)
+                    .collect();
+            assert_eq!(scalar[i], expected, "case {}", i);
+        }
+        Ok(())
+    }
+}
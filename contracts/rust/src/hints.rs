@@ -0,0 +1,219 @@
+//! Hints for the hint-assisted functions of the Solidity libraries
+//! (`invertWithHint`, `addWithHint`, `scalarMulWithHint` and
+//! `decompressWithHint`).
+//!
+//! Each hint is a value that is expensive to compute on-chain (an inverse, a
+//! square root, a scalar decomposition) but cheap to check, so the caller
+//! computes it off-chain from arkworks values and the library only verifies
+//! it.
+use crate::types::field_to_u256;
+use ark_ec::{short_weierstrass_jacobian::GroupAffine, SWModelParameters};
+use ark_ff::{field_new, to_bytes, Field, One, PrimeField, Zero};
+use ethers::prelude::*;
+use num_bigint::{BigInt, Sign};
+
+/// Parameters of the GLV endomorphism `phi(x, y) = (BETA * x, y)` of a curve
+/// with `a = 0`, as defined in the Solidity libraries.
+pub trait GlvParameters: SWModelParameters {
+    /// cube root of unity in the base field
+    const BETA: Self::BaseField;
+    /// cube root of unity in the scalar field, with `phi(P) = LAMBDA * P`
+    const LAMBDA: Self::ScalarField;
+    /// short basis `[(a1, b1), (a2, b2)]` (in decimal) of the lattice of the
+    /// `(a, b)` with `a + b * LAMBDA = 0 mod r`
+    const BASIS: [(&'static str, &'static str); 2];
+}
+
+impl GlvParameters for ark_pallas::PallasParameters {
+    const BETA: ark_pallas::Fq = field_new!(
+        ark_pallas::Fq,
+        "20444556541222657078399132219657928148671392403212669005631716460534733845831"
+    );
+    const LAMBDA: ark_pallas::Fr = field_new!(
+        ark_pallas::Fr,
+        "26005156700822196841419187675678338661165322343552424574062261873906994770353"
+    );
+    const BASIS: [(&'static str, &'static str); 2] = [
+        (
+            "98231058071100081932162823354453065728",
+            "-98231058071186745657228807397848383489",
+        ),
+        (
+            "196462116142286827589391630752301449217",
+            "98231058071100081932162823354453065728",
+        ),
+    ];
+}
+
+impl GlvParameters for ark_vesta::VestaParameters {
+    const BETA: ark_vesta::Fq = field_new!(
+        ark_vesta::Fq,
+        "2942865608506852014473558576493638302197734138389222805617480874486368177743"
+    );
+    const LAMBDA: ark_vesta::Fr = field_new!(
+        ark_vesta::Fr,
+        "8503465768106391777493614032514048814691664078728891710322960303815233784505"
+    );
+    const BASIS: [(&'static str, &'static str); 2] = [
+        (
+            "98231058071186745657228807397848383488",
+            "-98231058071100081932162823354453065729",
+        ),
+        (
+            "98231058071100081932162823354453065729",
+            "196462116142286827589391630752301449217",
+        ),
+    ];
+}
+
+/// Hint of `invertWithHint`, `None` for zero.
+pub fn inverse_hint<F: PrimeField>(f: F) -> Option<U256> {
+    f.inverse().map(field_to_u256)
+}
+
+/// Hint of `addWithHint`: the slope of the line through `p1` and `p2`, or of
+/// the tangent if they are equal. Zero when the hint is ignored, i.e. when
+/// `p1`, `p2` or their sum is the point of infinity.
+pub fn slope_hint<P: SWModelParameters>(p1: GroupAffine<P>, p2: GroupAffine<P>) -> U256
+where
+    P::BaseField: PrimeField,
+{
+    if p1.is_zero() || p2.is_zero() || (p1.x == p2.x && p1.y != p2.y) {
+        return U256::zero();
+    }
+    let lambda = if p1.x == p2.x {
+        (p1.x.square() * P::BaseField::from(3u64) + P::COEFF_A) / p1.y.double()
+    } else {
+        (p2.y - p1.y) / (p2.x - p1.x)
+    };
+    field_to_u256(lambda)
+}
+
+/// Hint of `scalarMulWithHint`: `(k1, k2)` with `s = k1 + k2 * LAMBDA mod r`
+/// and `|k1|, |k2| < 2^128`.
+pub fn glv_hint<P: GlvParameters>(s: P::ScalarField) -> (I256, I256)
+where
+    P::ScalarField: PrimeField,
+{
+    let (k1, k2) = glv_decompose::<P>(s);
+    (to_i256(&k1), to_i256(&k2))
+}
+
+/// Hint of `decompressWithHint`: a square root of `x^3 + a * x + b`, `None`
+/// if `x` is not the x-coordinate of a point.
+pub fn sqrt_hint<P: SWModelParameters>(x: P::BaseField) -> Option<U256>
+where
+    P::BaseField: PrimeField,
+{
+    GroupAffine::<P>::get_point_from_x(x, false).map(|p| field_to_u256(p.y))
+}
+
+/// Decomposition of `s` by rounding its coordinates in the basis of
+/// `P::BASIS`, see "Faster Point Multiplication on Elliptic Curves with
+/// Efficient Endomorphisms" (Gallant, Lambert, Vanstone), section 4.
+fn glv_decompose<P: GlvParameters>(s: P::ScalarField) -> (BigInt, BigInt)
+where
+    P::ScalarField: PrimeField,
+{
+    let n = to_bigint(-P::ScalarField::one()) + BigInt::from(1);
+    let parse = |v: &str| BigInt::parse_bytes(v.as_bytes(), 10).unwrap();
+    let (a1, b1) = (parse(P::BASIS[0].0), parse(P::BASIS[0].1));
+    let (a2, b2) = (parse(P::BASIS[1].0), parse(P::BASIS[1].1));
+
+    let k = to_bigint(s);
+    let c1 = round_div(&b2 * &k, &n);
+    let c2 = round_div(-&b1 * &k, &n);
+    let k1 = &k - &c1 * &a1 - &c2 * &a2;
+    let k2 = -&c1 * &b1 - &c2 * &b2;
+    debug_assert!(k1.bits() < 128 && k2.bits() < 128);
+    (k1, k2)
+}
+
+/// `a / n` rounded to the nearest integer, for `n > 0`.
+fn round_div(a: BigInt, n: &BigInt) -> BigInt {
+    let half = n / BigInt::from(2);
+    if a.sign() == Sign::Minus {
+        -((-a + half) / n)
+    } else {
+        (a + half) / n
+    }
+}
+
+fn to_bigint<F: PrimeField>(f: F) -> BigInt {
+    BigInt::from_bytes_le(Sign::Plus, &to_bytes!(f).unwrap())
+}
+
+fn to_i256(k: &BigInt) -> I256 {
+    let (sign, bytes) = k.to_bytes_be();
+    let abs = I256::from_raw(U256::from_big_endian(&bytes));
+    if sign == Sign::Minus {
+        -abs
+    } else {
+        abs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ark_ec::{short_weierstrass_jacobian::GroupProjective, AffineCurve, ProjectiveCurve};
+    use ark_std::UniformRand;
+
+    fn check_glv<P: GlvParameters>()
+    where
+        P::BaseField: PrimeField,
+        P::ScalarField: PrimeField,
+    {
+        let rng = &mut ark_std::test_rng();
+        assert!(P::COEFF_A.is_zero());
+        assert!(!P::BETA.is_one() && P::BETA.pow([3]).is_one());
+        assert!(!P::LAMBDA.is_one() && P::LAMBDA.pow([3]).is_one());
+
+        for _ in 0..10 {
+            let p = GroupProjective::<P>::rand(rng).into_affine();
+            let phi = GroupAffine::<P>::new(P::BETA * p.x, p.y, false);
+            assert_eq!(p.mul(P::LAMBDA.into_repr()).into_affine(), phi);
+        }
+
+        let edge_cases = vec![
+            P::ScalarField::zero(),
+            P::ScalarField::one(),
+            -P::ScalarField::one(),
+            P::LAMBDA,
+        ];
+        let random = (0..1000).map(|_| P::ScalarField::rand(rng));
+        for s in edge_cases.into_iter().chain(random) {
+            let (k1, k2) = glv_decompose::<P>(s);
+            assert!(k1.bits() < 128 && k2.bits() < 128);
+            let field = |k: &BigInt| {
+                let abs = P::ScalarField::from_le_bytes_mod_order(&k.to_bytes_le().1);
+                if k.sign() == Sign::Minus {
+                    -abs
+                } else {
+                    abs
+                }
+            };
+            assert_eq!(field(&k1) + field(&k2) * P::LAMBDA, s);
+        }
+    }
+
+    #[test]
+    fn pallas_glv() {
+        check_glv::<ark_pallas::PallasParameters>();
+    }
+
+    #[test]
+    fn vesta_glv() {
+        check_glv::<ark_vesta::VestaParameters>();
+    }
+
+    #[test]
+    fn i256_conversion() {
+        let k = BigInt::parse_bytes(b"-98231058071186745657228807397848383489", 10).unwrap();
+        assert_eq!(
+            to_i256(&k),
+            I256::from_dec_str("-98231058071186745657228807397848383489").unwrap()
+        );
+        assert_eq!(to_i256(&BigInt::from(0)), I256::zero());
+    }
+}
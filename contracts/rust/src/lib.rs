@@ -1,5 +1,24 @@
+pub mod accounts;
+pub mod artifacts;
 mod assertion;
+mod conformance;
+pub mod deployments;
 pub mod ethereum;
+pub mod gas;
+#[cfg(feature = "ark-0-3")]
+pub mod hash_to_field;
+#[cfg(feature = "ark-0-3")]
+pub mod hints;
+#[cfg(feature = "pasta-precompiles")]
+pub mod local_evm;
+mod multicall;
+mod overrides;
 mod pallas;
+mod pallas_ark04;
+#[cfg(feature = "pasta-precompiles")]
+pub mod precompiles;
+pub mod profiler;
+mod solc;
 pub mod types;
 mod vesta;
+mod vesta_ark04;
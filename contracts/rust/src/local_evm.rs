@@ -0,0 +1,302 @@
+//! An in-process EVM (SputnikVM) with the prototype Pasta precompiles of
+//! `precompiles`, to quantify what native Pallas and Vesta operations would
+//! save over the Solidity libraries.
+//!
+//! The EVM runs London rules on an in-memory state, every call is committed.
+//! Its precompiles are limited to modexp and the prototypes.
+use crate::{artifacts::Artifact, assertion::Revert, precompiles::PastaPrecompiles};
+use anyhow::{anyhow, Result};
+use ethers::{
+    abi::{Function, Token, Tokenize},
+    types::{Address, U256},
+};
+use evm::{
+    backend::{ApplyBackend, MemoryAccount, MemoryBackend, MemoryVicinity},
+    executor::stack::{MemoryStackState, StackExecutor, StackSubstateMetadata},
+    Config, CreateScheme, ExitReason,
+};
+use std::collections::BTreeMap;
+
+/// Gas limit of every transaction.
+pub const GAS_LIMIT: u64 = 30_000_000;
+
+pub struct LocalEvm {
+    config: Config,
+    vicinity: MemoryVicinity,
+    state: BTreeMap<Address, MemoryAccount>,
+    precompiles: PastaPrecompiles,
+    /// sender of all transactions
+    caller: Address,
+}
+
+impl LocalEvm {
+    /// A fresh chain, with the Pasta precompiles if `pasta` is set.
+    pub fn new(pasta: bool) -> Self {
+        let caller = Address::from_low_u64_be(0xca11e7);
+        let mut state = BTreeMap::new();
+        state.insert(
+            caller,
+            MemoryAccount {
+                nonce: U256::zero(),
+                balance: U256::max_value(),
+                storage: BTreeMap::new(),
+                code: vec![],
+            },
+        );
+        let vicinity = MemoryVicinity {
+            gas_price: U256::zero(),
+            origin: caller,
+            chain_id: U256::from(1337),
+            block_hashes: vec![],
+            block_number: U256::one(),
+            block_coinbase: Address::zero(),
+            block_timestamp: U256::one(),
+            block_difficulty: U256::zero(),
+            block_gas_limit: U256::from(GAS_LIMIT),
+            block_base_fee_per_gas: U256::zero(),
+        };
+        Self {
+            config: Config::london(),
+            vicinity,
+            state,
+            precompiles: PastaPrecompiles { pasta },
+            caller,
+        }
+    }
+
+    /// Install `code` at `address` without deployment, as the state overrides
+    /// of `overrides` do on a node.
+    pub fn set_code(&mut self, address: Address, code: Vec<u8>) {
+        self.state.entry(address).or_default().code = code;
+    }
+
+    /// Deploy the contract of `artifact`, which must not use external
+    /// libraries.
+    pub fn deploy<T: Tokenize>(
+        &mut self,
+        artifact: &Artifact,
+        constructor_args: T,
+    ) -> Result<Address> {
+        let mut init_code = artifact
+            .bytecode
+            .clone()
+            .into_bytes()
+            .ok_or_else(|| anyhow!("{} uses external libraries", artifact.contract_name))?
+            .to_vec();
+        init_code.extend(ethers::abi::encode(&constructor_args.into_tokens()));
+
+        let mut backend = MemoryBackend::new(&self.vicinity, self.state.clone());
+        let metadata = StackSubstateMetadata::new(GAS_LIMIT, &self.config);
+        let state = MemoryStackState::new(metadata, &backend);
+        let mut executor =
+            StackExecutor::new_with_precompiles(state, &self.config, &self.precompiles);
+        let address = executor.create_address(CreateScheme::Legacy {
+            caller: self.caller,
+        });
+        let (reason, _) =
+            executor.transact_create(self.caller, U256::zero(), init_code, GAS_LIMIT, vec![]);
+        if !reason.is_succeed() {
+            return Err(anyhow!(
+                "Deployment of {} failed: {:?}",
+                artifact.contract_name,
+                reason
+            ));
+        }
+
+        let (values, logs) = executor.into_state().deconstruct();
+        backend.apply(values, logs, false);
+        self.state = backend.state().clone();
+        Ok(address)
+    }
+
+    /// Send a transaction calling `to` with `data`. Returns its output and the
+    /// gas used, including the intrinsic gas as in a receipt.
+    pub fn call(&mut self, to: Address, data: Vec<u8>) -> Result<(Vec<u8>, u64)> {
+        let mut backend = MemoryBackend::new(&self.vicinity, self.state.clone());
+        let metadata = StackSubstateMetadata::new(GAS_LIMIT, &self.config);
+        let state = MemoryStackState::new(metadata, &backend);
+        let mut executor =
+            StackExecutor::new_with_precompiles(state, &self.config, &self.precompiles);
+        let (reason, output) =
+            executor.transact_call(self.caller, to, U256::zero(), data, GAS_LIMIT, vec![]);
+        let gas_used = executor.used_gas();
+        match reason {
+            ExitReason::Succeed(_) => {}
+            ExitReason::Revert(_) => {
+                return Err(anyhow!("Call reverted: {:?}", Revert::decode(&output)));
+            }
+            reason => return Err(anyhow!("Call failed: {:?}", reason)),
+        }
+
+        let (values, logs) = executor.into_state().deconstruct();
+        backend.apply(values, logs, false);
+        self.state = backend.state().clone();
+        Ok((output, gas_used))
+    }
+
+    /// `call` of `function` of the contract at `to`, with decoded output.
+    pub fn call_function(
+        &mut self,
+        to: Address,
+        function: &Function,
+        args: &[Token],
+    ) -> Result<(Vec<Token>, u64)> {
+        let (output, gas_used) = self.call(to, function.encode_input(args)?)?;
+        Ok((function.decode_output(&output)?, gas_used))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        gas::{self, GasMeasurement},
+        types::field_to_u256,
+    };
+    use ark_ec::{
+        short_weierstrass_jacobian::{GroupAffine, GroupProjective},
+        ProjectiveCurve, SWModelParameters,
+    };
+    use ark_ff::PrimeField;
+    use ark_std::UniformRand;
+
+    fn point<P: SWModelParameters>(p: GroupAffine<P>) -> Token
+    where
+        P::BaseField: PrimeField,
+    {
+        Token::Tuple(vec![
+            Token::Uint(field_to_u256(p.x)),
+            Token::Uint(field_to_u256(p.y)),
+        ])
+    }
+
+    /// Inputs of `add`, `scalarMul` and `multiScalarMul` (with 4 terms) on a
+    /// curve.
+    fn inputs<P: SWModelParameters>() -> Vec<(&'static str, usize, Vec<Token>)>
+    where
+        P::BaseField: PrimeField,
+        P::ScalarField: PrimeField,
+    {
+        let rng = &mut ark_std::test_rng();
+        let mut points: Vec<Token> = (0..6)
+            .map(|_| point(GroupProjective::<P>::rand(rng).into_affine()))
+            .collect();
+        let mut scalars: Vec<Token> = (0..5)
+            .map(|_| Token::Uint(field_to_u256(P::ScalarField::rand(rng))))
+            .collect();
+        let (p1, p2, s) = (
+            points.pop().unwrap(),
+            points.pop().unwrap(),
+            scalars.pop().unwrap(),
+        );
+        let (bases, scalars) = (points, scalars);
+        vec![
+            ("add", 1, vec![p1.clone(), p2]),
+            ("scalarMul", 1, vec![p1, s]),
+            (
+                "multiScalarMul",
+                4,
+                vec![Token::Array(bases), Token::Array(scalars)],
+            ),
+        ]
+    }
+
+    /// Gas of the library operations in `TestPallas`/`TestVesta` against the
+    /// precompiles called through `TestPrecompiles`, and against the shims
+    /// falling back to the libraries without precompiles.
+    fn compare<P: SWModelParameters>(curve: &str) -> Result<()>
+    where
+        P::BaseField: PrimeField,
+        P::ScalarField: PrimeField,
+    {
+        let library = Artifact::find(&format!("contracts/mocks/Test{0}.sol:Test{0}", curve))?;
+        let shims = Artifact::find("contracts/mocks/TestPrecompiles.sol:TestPrecompiles")?;
+        let prefix = curve.to_lowercase();
+
+        for pasta in [false, true] {
+            let mut evm = LocalEvm::new(pasta);
+            let library_address = evm.deploy(&library, ())?;
+            let shims_address = evm.deploy(&shims, ())?;
+
+            for (op, size, args) in inputs::<P>() {
+                let library_function = match op {
+                    "add" => "affineAdd",
+                    "scalarMul" => "affineScalarMul",
+                    _ => "testMultiScalarMul",
+                };
+                let shim_function = format!("{}{}{}", prefix, &op[..1].to_uppercase(), &op[1..]);
+                let (expected, library_gas) = evm.call_function(
+                    library_address,
+                    library.abi.function(library_function)?,
+                    &args,
+                )?;
+                let (output, shim_gas) =
+                    evm.call_function(shims_address, shims.abi.function(&shim_function)?, &args)?;
+                assert_eq!(output, expected, "{} of {}", shim_function, curve);
+                if pasta {
+                    assert!(shim_gas < library_gas, "{} is not cheaper", shim_function);
+                }
+
+                for (operation, gas_used) in [
+                    (
+                        format!("LocalEvm.Test{}.{}", curve, library_function),
+                        library_gas,
+                    ),
+                    (
+                        format!(
+                            "LocalEvm.TestPrecompiles.{}{}",
+                            shim_function,
+                            if pasta { "" } else { ".fallback" }
+                        ),
+                        shim_gas,
+                    ),
+                ] {
+                    gas::record(GasMeasurement {
+                        operation,
+                        input_size: size,
+                        estimated: gas_used,
+                        used: gas_used,
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn pallas_gas_comparison() -> Result<()> {
+        compare::<ark_pallas::PallasParameters>("Pallas")
+    }
+
+    #[test]
+    fn vesta_gas_comparison() -> Result<()> {
+        compare::<ark_vesta::VestaParameters>("Vesta")
+    }
+
+    /// A failing precompile only consumes its stipend: the MSM of a point off
+    /// the curve falls back to the library, which needs far more gas than
+    /// the 1/64 left by a precompile call given all the gas.
+    #[test]
+    fn fallback_on_invalid_points() -> Result<()> {
+        let rng = &mut ark_std::test_rng();
+        let shims = Artifact::find("contracts/mocks/TestPrecompiles.sol:TestPrecompiles")?;
+        let msm = shims.abi.function("pallasMultiScalarMul")?;
+        let mut bases: Vec<Token> = (0..4)
+            .map(|_| point(ark_pallas::Projective::rand(rng).into_affine()))
+            .collect();
+        bases[0] = Token::Tuple(vec![Token::Uint(U256::one()), Token::Uint(U256::one())]);
+        let scalars = (0..4)
+            .map(|_| Token::Uint(field_to_u256(ark_pallas::Fr::rand(rng))))
+            .collect();
+        let args = [Token::Array(bases), Token::Array(scalars)];
+
+        let mut outputs = vec![];
+        for pasta in [false, true] {
+            let mut evm = LocalEvm::new(pasta);
+            let address = evm.deploy(&shims, ())?;
+            outputs.push(evm.call_function(address, msm, &args)?.0);
+        }
+        assert_eq!(outputs[0], outputs[1]);
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+#![cfg_attr(debug_assertions, allow(dead_code))]
+//! Batched execution of contract calls through `Multicall.tryAggregate`.
+//!
+//! A `Batch` packs many calls of the same function, e.g. one per test vector,
+//! into a few `eth_call`s instead of one each, and decodes the result of every
+//! call into the return type of its abigen binding. The `Multicall` contract
+//! is deployed with CREATE2 on first use, so it has the same address on every
+//! chain.
+use crate::{
+    assertion::Revert,
+    ethereum::{artifact_path, deploy_create2, DeployConfig},
+};
+use anyhow::{anyhow, Result};
+use ethers::{
+    abi::{self, Detokenize, ParamType, Token},
+    contract::builders::ContractCall,
+    prelude::*,
+    types::transaction::eip2718::TypedTransaction,
+    utils::id,
+};
+use futures::future::try_join_all;
+use std::sync::Arc;
+
+/// Default number of calls per `eth_call`. Calls of a chunk share the gas cap
+/// of `eth_call` (50M on geth, the block gas limit on Hardhat), so lower it
+/// for expensive calls such as scalar multiplications.
+pub(crate) const DEFAULT_CHUNK_SIZE: usize = 100;
+
+/// Address of `Multicall`, deployed if needed.
+pub(crate) async fn multicall_address<M: 'static + Middleware>(client: Arc<M>) -> Result<Address> {
+    let path = artifact_path("contracts/Multicall.sol:Multicall")?;
+    let config = DeployConfig::from_env()?;
+    Ok(deploy_create2(client, &path, (), H256::zero(), &config)
+        .await?
+        .address())
+}
+
+/// Calls of the same function, executed together.
+pub(crate) struct Batch<M, D> {
+    client: Arc<M>,
+    calls: Vec<ContractCall<M, D>>,
+    chunk_size: usize,
+}
+
+impl<M: 'static + Middleware, D: Detokenize> Batch<M, D> {
+    pub fn new(client: Arc<M>) -> Self {
+        Self {
+            client,
+            calls: Vec::new(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must be positive");
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    pub fn add(&mut self, call: ContractCall<M, D>) -> &mut Self {
+        self.calls.push(call);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    /// Outcome of every call, in the order they were added: its decoded
+    /// output or its revert.
+    pub async fn call(&self) -> Result<Vec<std::result::Result<D, Revert>>> {
+        if self.calls.is_empty() {
+            return Ok(vec![]);
+        }
+        let multicall = multicall_address(self.client.clone()).await?;
+        let chunks = try_join_all(
+            self.calls
+                .chunks(self.chunk_size)
+                .map(|chunk| self.aggregate(multicall, chunk)),
+        )
+        .await?;
+
+        let mut outcomes = Vec::with_capacity(self.calls.len());
+        for (index, (call, (success, data))) in self
+            .calls
+            .iter()
+            .zip(chunks.into_iter().flatten())
+            .enumerate()
+        {
+            outcomes.push(if success {
+                let output = call
+                    .function
+                    .decode_output(&data)
+                    .map_err(|e| e.to_string())
+                    .and_then(|tokens| D::from_tokens(tokens).map_err(|e| e.to_string()))
+                    .map_err(|e| {
+                        anyhow!(
+                            "Unable to decode the output of call {} of the batch: {}",
+                            index,
+                            e
+                        )
+                    })?;
+                Ok(output)
+            } else {
+                Err(Revert::decode(&data)
+                    .unwrap_or_else(|| Revert::Unknown("no revert data".to_string())))
+            });
+        }
+        Ok(outcomes)
+    }
+
+    /// Decoded output of every call, failing with the index of the first call
+    /// that reverted.
+    pub async fn call_all(&self) -> Result<Vec<D>> {
+        self.call()
+            .await?
+            .into_iter()
+            .enumerate()
+            .map(|(index, outcome)| {
+                outcome.map_err(|revert| {
+                    anyhow!(
+                        "Call {} of the batch ({}) reverted: {:?}",
+                        index,
+                        self.calls[index].function.name,
+                        revert
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Success and return (or revert) data of each call of `chunk`.
+    async fn aggregate(
+        &self,
+        multicall: Address,
+        chunk: &[ContractCall<M, D>],
+    ) -> Result<Vec<(bool, Vec<u8>)>> {
+        let calls = chunk
+            .iter()
+            .map(|call| {
+                let target = match call.tx.to() {
+                    Some(NameOrAddress::Address(address)) => *address,
+                    to => return Err(anyhow!("Unsupported call target {:?}", to)),
+                };
+                let data = call.tx.data().map(|d| d.to_vec()).unwrap_or_default();
+                Ok(Token::Tuple(vec![
+                    Token::Address(target),
+                    Token::Bytes(data),
+                ]))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut data = id("tryAggregate((address,bytes)[])").to_vec();
+        data.extend(abi::encode(&[Token::Array(calls)]));
+        let tx: TypedTransaction = TransactionRequest::new().to(multicall).data(data).into();
+        let output = self
+            .client
+            .call(&tx, None)
+            .await
+            .map_err(|e| anyhow!("Multicall.tryAggregate failed: {}", e))?;
+
+        let result_type = ParamType::Array(Box::new(ParamType::Tuple(vec![
+            ParamType::Bool,
+            ParamType::Bytes,
+        ])));
+        let results = match abi::decode(&[result_type], &output)?.pop() {
+            Some(Token::Array(results)) => results,
+            _ => return Err(anyhow!("Unexpected output of Multicall.tryAggregate")),
+        };
+        results
+            .into_iter()
+            .map(|result| match result {
+                Token::Tuple(fields) => match &fields[..] {
+                    [Token::Bool(success), Token::Bytes(data)] => Ok((*success, data.clone())),
+                    _ => Err(anyhow!("Unexpected result of Multicall.tryAggregate")),
+                },
+                _ => Err(anyhow!("Unexpected result of Multicall.tryAggregate")),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        assertion::{panic_code, Matcher},
+        deployments::deploy_or_reuse,
+        ethereum::get_funded_deployer,
+        types::TestErrors,
+    };
+
+    #[tokio::test]
+    async fn test_batch() -> Result<()> {
+        let client = get_funded_deployer().await?;
+        let path = artifact_path("contracts/mocks/TestErrors.sol:TestErrors")?;
+        let contract = deploy_or_reuse(client.clone(), &path, ()).await?;
+        let contract = TestErrors::new(contract.address(), client.clone());
+
+        // several chunks, and a vector that fails in the middle
+        let mut batch = Batch::new(client.clone()).chunk_size(7);
+        for i in 0..20u64 {
+            let divisor: u64 = if i == 13 { 0 } else { 2 };
+            batch.add(contract.divide((2 * i).into(), divisor.into()));
+        }
+        let outcomes = batch.call().await?;
+        assert_eq!(outcomes.len(), 20);
+        for (i, outcome) in outcomes.into_iter().enumerate() {
+            match outcome {
+                Ok(res) => assert_eq!(res, U256::from(i)),
+                Err(revert) => {
+                    assert_eq!(i, 13);
+                    Some(revert).should_revert_with_panic(panic_code::DIVISION_BY_ZERO);
+                }
+            }
+        }
+
+        let error = batch.call_all().await.unwrap_err().to_string();
+        assert!(error.starts_with("Call 13 of the batch (divide) reverted"));
+
+        batch = Batch::new(client.clone());
+        batch.add(contract.add(1.into(), 2.into()));
+        assert_eq!(batch.call_all().await?, vec![U256::from(3)]);
+        Ok(())
+    }
+}
@@ -0,0 +1,300 @@
+#![cfg_attr(debug_assertions, allow(dead_code))]
+//! Calls to code that is not deployed, through the state override set of
+//! `eth_call`.
+//!
+//! A `Scratch` contract is the runtime code of a compiled contract, installed
+//! at `scratch_address()` for the duration of a single `eth_call`. Internal
+//! library functions can thus be tested through a small wrapper contract,
+//! compiled by `build.rs` or on the fly with `compile`, without a deployment
+//! transaction. Constructors never run: storage starts empty and immutables
+//! are not set.
+//!
+//! The state override set is the third parameter of `eth_call` in geth (and
+//! in nodes following its API, such as anvil), Hardhat does not support it:
+//! check with `supports_state_overrides`, or, with the `pasta-precompiles`
+//! feature, run the scratch code in a `LocalEvm` with `Scratch::call_local`.
+#[cfg(feature = "pasta-precompiles")]
+use crate::local_evm::LocalEvm;
+use crate::{artifacts::Artifact, solc};
+use anyhow::{anyhow, Result};
+use ethers::{
+    abi::{Abi, Detokenize},
+    contract::{builders::ContractCall, Contract},
+    prelude::*,
+    types::transaction::eip2718::TypedTransaction,
+};
+use serde::Serialize;
+use serde_json::json;
+use std::{collections::BTreeMap, path::Path, sync::Arc};
+
+/// Address where the code of `Scratch` contracts is installed.
+pub(crate) fn scratch_address() -> Address {
+    Address::repeat_byte(0x5c)
+}
+
+/// Overridden fields of one account, `None` fields keep their state.
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AccountOverride {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<U64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<Bytes>,
+    /// replaces the whole storage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<BTreeMap<H256, H256>>,
+    /// replaces the given storage slots only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_diff: Option<BTreeMap<H256, H256>>,
+}
+
+/// State override set of `eth_call`, by account.
+pub(crate) type StateOverride = BTreeMap<Address, AccountOverride>;
+
+/// `eth_call` of `tx` on the latest block, with the state of the accounts in
+/// `overrides` replaced.
+pub(crate) async fn call_with_overrides<M: Middleware>(
+    client: &M,
+    tx: &TypedTransaction,
+    overrides: &StateOverride,
+) -> Result<Bytes, ProviderError> {
+    client
+        .provider()
+        .request("eth_call", (tx, "latest", overrides))
+        .await
+}
+
+/// Whether the node of `client` applies the state overrides of `eth_call`:
+/// it must return what code installed at `scratch_address()` returns, nodes
+/// may reject the third parameter as well as ignore it.
+pub(crate) async fn supports_state_overrides<M: Middleware>(client: &M) -> bool {
+    // PUSH1 42 PUSH1 0 MSTORE PUSH1 32 PUSH1 0 RETURN
+    let code = vec![0x60, 0x2a, 0x60, 0x00, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3];
+    let mut overrides = StateOverride::new();
+    overrides.insert(
+        scratch_address(),
+        AccountOverride {
+            code: Some(code.into()),
+            ..Default::default()
+        },
+    );
+    let tx: TypedTransaction = TransactionRequest::new().to(scratch_address()).into();
+    match call_with_overrides(client, &tx, &overrides).await {
+        Ok(output) => output.as_ref() == H256::from_low_u64_be(42).as_bytes(),
+        Err(_) => false,
+    }
+}
+
+/// Compile `source` with `SOLC_PATH` (or `solc` from the `PATH`) and return
+/// the artifact of its contract `name`. The source unit name is
+/// `contracts/scratch/<name>.sol`, so that it imports the libraries as the
+/// mocks do (`../libraries/Pallas.sol`).
+pub(crate) fn compile(name: &str, source: &str) -> Result<Artifact> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let source_name = format!("contracts/scratch/{}.sol", name);
+    let input = json!({
+        "language": "Solidity",
+        "sources": { &source_name: { "content": source } },
+        "settings": {
+            "optimizer": { "enabled": true, "runs": 200 },
+            "outputSelection": {
+                "*": { "*": ["abi", "evm.bytecode", "evm.deployedBytecode", "metadata"] }
+            }
+        }
+    });
+
+    let output = solc::compile_standard_json(&input, Some(&root)).map_err(|e| anyhow!(e))?;
+    let contract = &output["contracts"][&source_name][name];
+    if contract.is_null() {
+        return Err(anyhow!("No contract {} in {}", name, source_name));
+    }
+    Artifact::from_standard_json(&source_name, name, contract).map_err(|e| anyhow!("{}", e))
+}
+
+/// Runtime code called at `scratch_address()` through state overrides.
+pub(crate) struct Scratch<M> {
+    client: Arc<M>,
+    abi: Abi,
+    code: Bytes,
+}
+
+impl<M: 'static + Middleware> Scratch<M> {
+    pub fn new(client: Arc<M>, artifact: Artifact) -> Result<Self> {
+        let code = artifact.deployed_bytecode.into_bytes().ok_or_else(|| {
+            anyhow!(
+                "{} uses external libraries, scratch code must be linked",
+                artifact.contract_name
+            )
+        })?;
+        Ok(Self {
+            client,
+            abi: artifact.abi,
+            code,
+        })
+    }
+
+    /// Scratch code of the contract `fully_qualified_name` compiled by
+    /// `build.rs`, whose abigen binding at `scratch_address()` builds the
+    /// calls.
+    pub fn from_artifact(client: Arc<M>, fully_qualified_name: &str) -> Result<Self> {
+        Self::new(client, Artifact::find(fully_qualified_name)?)
+    }
+
+    /// Scratch code of the contract `name` in `source`, see `compile`.
+    pub fn compile(client: Arc<M>, name: &str, source: &str) -> Result<Self> {
+        Self::new(client, compile(name, source)?)
+    }
+
+    /// The contract at `scratch_address()`, to build calls to contracts
+    /// without abigen binding with `Contract::method`.
+    pub fn contract(&self) -> Contract<M> {
+        Contract::new(scratch_address(), self.abi.clone(), self.client.clone())
+    }
+
+    /// Execute `call` with the scratch code installed at its target. The
+    /// error is a `ContractError`, so that `assertion::Matcher` applies.
+    pub async fn call<D: Detokenize>(
+        &self,
+        call: &ContractCall<M, D>,
+    ) -> Result<D, ContractError<M>> {
+        let mut overrides = StateOverride::new();
+        overrides.insert(
+            scratch_address(),
+            AccountOverride {
+                code: Some(self.code.clone()),
+                ..Default::default()
+            },
+        );
+        let output = call_with_overrides(self.client.as_ref(), &call.tx, &overrides).await?;
+        let tokens = call.function.decode_output(output.as_ref())?;
+        Ok(D::from_tokens(tokens)?)
+    }
+
+    /// Execute `call` in `evm` instead of the node, with the scratch code
+    /// installed at `scratch_address()`, for nodes without state overrides.
+    /// Unlike `call`, the call is committed to the state of `evm`.
+    #[cfg(feature = "pasta-precompiles")]
+    pub fn call_local<D: Detokenize>(
+        &self,
+        evm: &mut LocalEvm,
+        call: &ContractCall<M, D>,
+    ) -> Result<D> {
+        evm.set_code(scratch_address(), self.code.to_vec());
+        let data = call.tx.data().map(|d| d.to_vec()).unwrap_or_default();
+        let (output, _) = evm.call(scratch_address(), data)?;
+        let tokens = call.function.decode_output(&output)?;
+        Ok(D::from_tokens(tokens)?)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        assertion::{panic_code, Matcher},
+        ethereum::get_funded_deployer,
+        types::TestErrors,
+    };
+    #[cfg(feature = "ark-0-3")]
+    use {crate::types::field_to_u256, ark_ec::AffineCurve};
+
+    #[test]
+    fn state_override_serialization() {
+        let mut overrides = StateOverride::new();
+        overrides.insert(
+            scratch_address(),
+            AccountOverride {
+                code: Some(vec![0x60, 0x00].into()),
+                state_diff: Some(
+                    vec![(H256::zero(), H256::from_low_u64_be(1))]
+                        .into_iter()
+                        .collect(),
+                ),
+                ..Default::default()
+            },
+        );
+        let json = serde_json::to_value(&overrides).unwrap();
+        let account = &json[format!("{:?}", scratch_address())];
+        assert_eq!(account["code"], "0x6000");
+        assert_eq!(
+            account["stateDiff"][format!("{:?}", H256::zero())],
+            format!("{:?}", H256::from_low_u64_be(1))
+        );
+        assert!(account.get("balance").is_none());
+    }
+
+    #[ignore = "requires state overrides, run with --include-ignored on geth"]
+    #[tokio::test]
+    async fn test_scratch_calls() -> Result<()> {
+        let client = get_funded_deployer().await?;
+        assert!(
+            supports_state_overrides(client.as_ref()).await,
+            "the node does not support state overrides"
+        );
+
+        // a wrapper compiled by build.rs, called through its abigen binding
+        let scratch =
+            Scratch::from_artifact(client.clone(), "contracts/mocks/TestErrors.sol:TestErrors")?;
+        let contract = TestErrors::new(scratch_address(), client.clone());
+        assert_eq!(
+            scratch.call(&contract.divide(6.into(), 2.into())).await?,
+            U256::from(3)
+        );
+        scratch
+            .call(&contract.divide(1.into(), 0.into()))
+            .await
+            .should_revert_with_panic(panic_code::DIVISION_BY_ZERO);
+
+        // a wrapper of an internal library function, compiled on the fly
+        #[cfg(feature = "ark-0-3")]
+        {
+            let scratch = Scratch::compile(
+                client.clone(),
+                "PallasScratch",
+                r#"
+            pragma solidity ^0.8.0;
+            import {Pallas as C} from "../libraries/Pallas.sol";
+
+            contract PallasScratch {
+                function doubleGenerator() public view returns (uint256, uint256) {
+                    C.PallasAffinePoint memory p = C.double(C.AffineGenerator());
+                    return (p.x, p.y);
+                }
+            }
+            "#,
+            )?;
+            let call = scratch
+                .contract()
+                .method::<_, (U256, U256)>("doubleGenerator", ())?;
+            let g = ark_pallas::Affine::prime_subgroup_generator();
+            assert_eq!(
+                scratch.call(&call).await?,
+                (field_to_u256((g + g).x), field_to_u256((g + g).y))
+            );
+        }
+        Ok(())
+    }
+
+    // without node: the calls are only built by the client
+    #[cfg(feature = "pasta-precompiles")]
+    #[test]
+    fn test_local_scratch_calls() -> Result<()> {
+        use std::convert::TryFrom;
+
+        let client = Arc::new(Provider::<Http>::try_from("http://localhost:8545")?);
+        let scratch =
+            Scratch::from_artifact(client.clone(), "contracts/mocks/TestErrors.sol:TestErrors")?;
+        let contract = TestErrors::new(scratch_address(), client);
+        let mut evm = LocalEvm::new(false);
+        assert_eq!(
+            scratch.call_local(&mut evm, &contract.divide(6.into(), 2.into()))?,
+            U256::from(3)
+        );
+        assert!(scratch
+            .call_local(&mut evm, &contract.divide(1.into(), 0.into()))
+            .is_err());
+        Ok(())
+    }
+}
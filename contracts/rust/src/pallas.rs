@@ -1,11 +1,15 @@
-#![cfg(test)]
+#![cfg(all(test, feature = "ark-0-3"))]
 
 use crate::{
-    assertion::Matcher,
-    ethereum::{deploy, get_funded_deployer},
+    assertion::{GasMatcher, Matcher},
+    deployments::deploy_or_reuse,
+    ethereum::{artifact_path, get_funded_deployer, EthMiddleware},
+    gas, hints,
+    multicall::Batch,
+    profiler,
     types::{
-        field_to_u256, PallasAffinePoint as AffinePoint, PallasProjectivePoint as ProjectivePoint,
-        TestPallas,
+        field_to_u256, is_y_even, is_y_greatest, is_y_negative, u256_to_field, y_parity,
+        PallasAffinePoint as AffinePoint, PallasProjectivePoint as ProjectivePoint, TestPallas,
     },
 };
 use anyhow::Result;
@@ -16,18 +20,25 @@ use ark_ff::{field_new, to_bytes, Field};
 use ark_ff::{FpParameters, PrimeField};
 use ark_pallas::{Affine, Fq, Fr, Projective};
 use ark_std::UniformRand;
-use ark_std::Zero;
-use ethers::core::k256::ecdsa::SigningKey;
+use ark_std::{One, Zero};
 use ethers::prelude::*;
 use rand::RngCore;
 use std::path::Path;
 
-async fn deploy_contract(
-) -> Result<TestPallas<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>> {
+/// Gas ceilings, with some margin over the reference numbers in the README.
+const PROJECTIVE_SCALAR_MUL_GAS: u64 = 450_000;
+/// a projective scalar mul and one inversion
+const AFFINE_SCALAR_MUL_GAS: u64 = 460_000;
+/// per term of `multiScalarMul`, a projective scalar mul and one addition
+const MULTI_SCALAR_MUL_GAS: u64 = 460_000;
+/// two projective scalar muls sharing their doublings
+const DOUBLE_SCALAR_MUL_GAS: u64 = 600_000;
+
+async fn deploy_contract() -> Result<TestPallas<EthMiddleware>> {
     let client = get_funded_deployer().await.unwrap();
-    let contract = deploy(
+    let contract = deploy_or_reuse(
         client.clone(),
-        Path::new("../abi/contracts/mocks/TestPallas.sol/TestPallas"),
+        &artifact_path("contracts/mocks/TestPallas.sol:TestPallas")?,
         (),
     )
     .await
@@ -42,50 +53,65 @@ async fn test_add() -> Result<()> {
 
     let p1 = Projective::rand(rng);
     let p2 = Projective::rand(rng);
-    println!(
-        "gas cost: affine addition: {}",
-        contract
-            .affine_add(p1.into_affine().into(), p2.into_affine().into())
-            .estimate_gas()
-            .await?
-    );
+    gas::measure(
+        "TestPallas.affineAdd",
+        1,
+        contract.affine_add(p1.into_affine().into(), p2.into_affine().into()),
+    )
+    .await?;
 
     let p1 = Projective::rand(rng);
     let p2 = Projective::rand(rng);
-    println!(
-        "gas cost: projective addition: {}",
-        contract
-            .projective_add(p1.into(), p2.into())
-            .estimate_gas()
-            .await?
-    );
-
-    for _ in 0..10 {
+    gas::measure(
+        "TestPallas.projectiveAdd",
+        1,
+        contract.projective_add(p1.into(), p2.into()),
+    )
+    .await?;
+
+    // test vectors, each batch runs in a single eth_call
+    let client = get_funded_deployer().await?;
+    let mut affine_batch = Batch::new(client.clone());
+    let mut affine_expected: Vec<AffinePoint> = Vec::new();
+    let mut projective_batch = Batch::new(client);
+    let mut projective_expected: Vec<ProjectivePoint> = Vec::new();
+    for _ in 0..100 {
         let p1: Affine = Projective::rand(rng).into();
         let p2: Affine = Projective::rand(rng).into();
-        let res: AffinePoint = contract
-            .affine_add(p1.into(), p2.into())
-            .call()
-            .await?
-            .into();
-        assert_eq!(res, (p1 + p2).into());
+        affine_batch.add(contract.affine_add(p1.into(), p2.into()));
+        affine_expected.push((p1 + p2).into());
 
         let p1 = Projective::rand(rng);
         let p2 = Projective::rand(rng);
-        let res: ProjectivePoint = contract
-            .projective_add(p1.into(), p2.into())
-            .call()
-            .await?
-            .into();
-        assert_eq!(res, (p1 + p2).into());
+        projective_batch.add(contract.projective_add(p1.into(), p2.into()));
+        projective_expected.push((p1 + p2).into());
 
         let p1 = Projective::rand(rng);
-        let res: ProjectivePoint = contract
-            .projective_add(p1.into(), p1.into())
-            .call()
-            .await?
-            .into();
-        assert_eq!(res, (p1 + p1).into());
+        projective_batch.add(contract.projective_add(p1.into(), p1.into()));
+        projective_expected.push((p1 + p1).into());
+    }
+    for (i, (res, expected)) in affine_batch
+        .call_all()
+        .await?
+        .into_iter()
+        .zip(affine_expected)
+        .enumerate()
+    {
+        assert_eq!(AffinePoint::from(res), expected, "affine vector {}", i);
+    }
+    for (i, (res, expected)) in projective_batch
+        .call_all()
+        .await?
+        .into_iter()
+        .zip(projective_expected)
+        .enumerate()
+    {
+        assert_eq!(
+            ProjectivePoint::from(res),
+            expected,
+            "projective vector {}",
+            i
+        );
     }
 
     // test point of infinity, O_E + P = P
@@ -136,10 +162,7 @@ async fn test_info_affine() -> Result<()> {
     assert_eq!(zero_sol_affine, zero.into());
 
     let p = Projective::rand(rng);
-    println!(
-        "gas cost: to affine: {}",
-        contract.to_affine(p.into()).estimate_gas().await?
-    );
+    gas::measure("TestPallas.toAffine", 1, contract.to_affine(p.into())).await?;
 
     for _ in 0..10 {
         let p = Projective::rand(rng);
@@ -169,10 +192,7 @@ async fn test_info_projective() -> Result<()> {
     assert_eq!(zero_sol_affine, zero.into());
 
     let p = Projective::rand(rng);
-    println!(
-        "gas cost: to affine: {}",
-        contract.to_affine(p.into()).estimate_gas().await?
-    );
+    gas::measure("TestPallas.toAffine", 1, contract.to_affine(p.into())).await?;
 
     for _ in 0..10 {
         let p = Projective::rand(rng);
@@ -180,6 +200,20 @@ async fn test_info_projective() -> Result<()> {
         assert_eq!(p_sol, p.into_affine());
     }
 
+    // (x * l^2, y * l^3, l) is (x, y) in Jacobian coordinates
+    for _ in 0..10 {
+        let p = Projective::rand(rng).into_affine();
+        let l = Fq::rand(rng);
+        assert!(!l.is_zero() && !l.is_one());
+        let q = ProjectivePoint {
+            x: field_to_u256(p.x * l.square()),
+            y: field_to_u256(p.y * l.square() * l),
+            z: field_to_u256(l),
+        };
+        let q_sol: Affine = contract.to_affine(q).call().await?.into();
+        assert_eq!(q_sol, p);
+    }
+
     Ok(())
 }
 
@@ -204,6 +238,17 @@ async fn test_is_infinity() -> Result<()> {
         );
     }
 
+    // only (0, 0, 0) is the point of infinity, not (x, y, 0) nor (0, 0, z)
+    let p = Projective::rand(rng).into_affine();
+    for (x, y, z) in [(p.x, p.y, Fq::zero()), (Fq::zero(), Fq::zero(), Fq::one())] {
+        let point = ProjectivePoint {
+            x: field_to_u256(x),
+            y: field_to_u256(y),
+            z: field_to_u256(z),
+        };
+        assert!(!contract.is_projective_infinity(point).call().await?);
+    }
+
     Ok(())
 }
 
@@ -233,23 +278,66 @@ async fn test_scalar_mul() -> Result<()> {
 
     let p = Projective::rand(rng);
     let s = Fr::rand(rng);
-    println!(
-        "gas cost: affine scalar mul: {}",
-        contract
-            .affine_scalar_mul(p.into_affine().into(), field_to_u256(s))
-            .estimate_gas()
-            .await?
-    );
+    gas::measure(
+        "TestPallas.projectiveScalarMul",
+        1,
+        contract.projective_scalar_mul(p.into(), field_to_u256(s)),
+    )
+    .await?
+    .should_cost_at_most(PROJECTIVE_SCALAR_MUL_GAS);
 
-    let p = Projective::rand(rng);
+    // the affine scalar mul computes in Jacobian coordinates, it matches the
+    // former implementation inverting in every step for a fraction of its gas
+    let p = Projective::rand(rng).into_affine();
     let s = Fr::rand(rng);
-    println!(
-        "gas cost: projective scalar mul: {}",
-        contract
-            .projective_scalar_mul(p.into(), field_to_u256(s))
-            .estimate_gas()
-            .await?
-    );
+    let jacobian = gas::measure(
+        "TestPallas.affineScalarMul",
+        1,
+        contract.affine_scalar_mul(p.into(), field_to_u256(s)),
+    )
+    .await?
+    .should_cost_at_most(AFFINE_SCALAR_MUL_GAS);
+    let with_inversions = gas::measure(
+        "TestPallas.affineScalarMulWithInversions",
+        1,
+        contract.affine_scalar_mul_with_inversions(p.into(), field_to_u256(s)),
+    )
+    .await?;
+    assert!(jacobian.used * 2 < with_inversions.used);
+
+    let client = get_funded_deployer().await?;
+    let mut jacobian_batch = Batch::new(client.clone()).chunk_size(10);
+    let mut inversions_batch = Batch::new(client).chunk_size(10);
+    let mut expected: Vec<AffinePoint> = Vec::new();
+    for i in 0..20 {
+        let p = Projective::rand(rng);
+        // small scalars as edge cases
+        let s = if i < 3 {
+            Fr::from(i as u64 + 1)
+        } else {
+            Fr::rand(rng)
+        };
+        let args = (p.into_affine().into(), field_to_u256(s));
+        jacobian_batch.add(contract.affine_scalar_mul(args.0.clone(), args.1));
+        inversions_batch.add(contract.affine_scalar_mul_with_inversions(args.0, args.1));
+        expected.push(Group::mul(&p, &s).into_affine().into());
+    }
+    let jacobian = jacobian_batch.call_all().await?;
+    let with_inversions = inversions_batch.call_all().await?;
+    for (i, expected) in expected.into_iter().enumerate() {
+        assert_eq!(
+            AffinePoint::from(jacobian[i].clone()),
+            expected,
+            "vector {}",
+            i
+        );
+        assert_eq!(
+            AffinePoint::from(with_inversions[i].clone()),
+            expected,
+            "vector {}",
+            i
+        );
+    }
 
     for _ in 0..10 {
         let p = Projective::rand(rng);
@@ -310,14 +398,33 @@ async fn test_multi_scalar_mul() -> Result<()> {
         let s_solidity: Vec<U256> = s_rust.iter().map(|&x| field_to_u256(x)).collect();
         let s_rust: Vec<_> = s_rust.iter().map(|&x| x.into_repr()).collect();
 
-        println!(
-            "gas cost: {} msm: {}",
+        let jacobian = gas::measure(
+            "TestPallas.multiScalarMul",
             length,
-            contract
-                .test_multi_scalar_mul(p_solidity.clone(), s_solidity.clone())
-                .estimate_gas()
+            contract.test_multi_scalar_mul(p_solidity.clone(), s_solidity.clone()),
+        )
+        .await?
+        .should_cost_at_most(MULTI_SCALAR_MUL_GAS * length as u64);
+        if length <= 3 {
+            let with_inversions = gas::measure(
+                "TestPallas.multiScalarMulWithInversions",
+                length,
+                contract.multi_scalar_mul_with_inversions(p_solidity.clone(), s_solidity.clone()),
+            )
+            .await?;
+            let res: AffinePoint = contract
+                .multi_scalar_mul_with_inversions(p_solidity.clone(), s_solidity.clone())
+                .call()
                 .await?
-        );
+                .into();
+            assert_eq!(
+                res,
+                VariableBaseMSM::multi_scalar_mul(&p_rust, &s_rust)
+                    .into_affine()
+                    .into()
+            );
+            assert!(jacobian.used * 2 < with_inversions.used);
+        }
 
         let res: AffinePoint = contract
             .test_multi_scalar_mul(p_solidity, s_solidity)
@@ -336,6 +443,203 @@ async fn test_multi_scalar_mul() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_double_scalar_mul() -> Result<()> {
+    let rng = &mut ark_std::test_rng();
+    let contract = deploy_contract().await?;
+    type Parameters = ark_pallas::PallasParameters;
+
+    let (p, q): (Affine, Affine) = (Projective::rand(rng).into(), Projective::rand(rng).into());
+    let (a, b) = (Fr::rand(rng), Fr::rand(rng));
+    let straus = gas::measure(
+        "TestPallas.affineDoubleScalarMul",
+        1,
+        contract.affine_double_scalar_mul(p.into(), field_to_u256(a), q.into(), field_to_u256(b)),
+    )
+    .await?
+    .should_cost_at_most(DOUBLE_SCALAR_MUL_GAS);
+    let (k1, k2) = hints::glv_hint::<Parameters>(a);
+    let (k3, k4) = hints::glv_hint::<Parameters>(b);
+    let glv = gas::measure(
+        "TestPallas.affineDoubleScalarMulWithHint",
+        1,
+        contract.affine_double_scalar_mul_with_hint(
+            p.into(),
+            field_to_u256(a),
+            q.into(),
+            field_to_u256(b),
+            [k1, k2, k3, k4],
+        ),
+    )
+    .await?;
+    assert!(glv.used < straus.used);
+
+    // random scalars, and edge scalars on random and related points
+    let mut vectors = vec![];
+    for _ in 0..10 {
+        let (p, q) = (Projective::rand(rng), Projective::rand(rng));
+        vectors.push((p, Fr::rand(rng), q, Fr::rand(rng)));
+    }
+    let (p, q) = (Projective::rand(rng), Projective::rand(rng));
+    let s = Fr::rand(rng);
+    let one = Fr::from(1u64);
+    vectors.extend(vec![
+        (p, Fr::zero(), q, Fr::zero()),
+        (p, Fr::zero(), q, s),
+        (p, s, q, Fr::zero()),
+        (p, one, q, one),
+        (p, -one, q, -one),
+        (p, one, p, -one),
+        (p, s, p, -s),
+        (p, s, p, s),
+        (p, s, -p, s),
+        (p, s, Projective::zero(), s),
+    ]);
+
+    let client = get_funded_deployer().await?;
+    let mut affine_batch = Batch::new(client.clone()).chunk_size(10);
+    let mut projective_batch = Batch::new(client.clone()).chunk_size(10);
+    let mut glv_batch = Batch::new(client).chunk_size(10);
+    let mut expected: Vec<Projective> = vec![];
+    for &(p, a, q, b) in vectors.iter() {
+        let (pa, qa): (Affine, Affine) = (p.into(), q.into());
+        let (a_sol, b_sol) = (field_to_u256(a), field_to_u256(b));
+        affine_batch.add(contract.affine_double_scalar_mul(pa.into(), a_sol, qa.into(), b_sol));
+        projective_batch.add(contract.projective_double_scalar_mul(
+            p.into(),
+            a_sol,
+            q.into(),
+            b_sol,
+        ));
+        let (k1, k2) = hints::glv_hint::<Parameters>(a);
+        let (k3, k4) = hints::glv_hint::<Parameters>(b);
+        glv_batch.add(contract.affine_double_scalar_mul_with_hint(
+            pa.into(),
+            a_sol,
+            qa.into(),
+            b_sol,
+            [k1, k2, k3, k4],
+        ));
+        expected.push(Group::mul(&p, &a) + Group::mul(&q, &b));
+    }
+    let affine = affine_batch.call_all().await?;
+    let projective = projective_batch.call_all().await?;
+    let glv = glv_batch.call_all().await?;
+    for (i, expected) in expected.into_iter().enumerate() {
+        let expected_affine: AffinePoint = expected.into_affine().into();
+        assert_eq!(
+            AffinePoint::from(affine[i].clone()),
+            expected_affine,
+            "vector {}",
+            i
+        );
+        assert_eq!(
+            AffinePoint::from(glv[i].clone()),
+            expected_affine,
+            "vector {}",
+            i
+        );
+        let res: Projective = projective[i].clone().into();
+        assert_eq!(res.into_affine(), expected.into_affine(), "vector {}", i);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_scalar_policies() -> Result<()> {
+    let rng = &mut ark_std::test_rng();
+    let contract = deploy_contract().await?;
+
+    let r = field_to_u256(-Fr::from(1u64)) + U256::one();
+    let scalars = vec![
+        U256::zero(),
+        U256::one(),
+        r - U256::one(),
+        r,
+        r + U256::one(),
+        U256::one() << 255,
+        U256::max_value(),
+    ];
+    let p = Projective::rand(rng);
+    for &s in scalars.iter() {
+        // arkworks multiplies by the integer s, which is s mod r in a group of
+        // order r
+        let expected: AffinePoint = ProjectiveCurve::mul(p, s.0).into_affine().into();
+        assert_eq!(
+            expected,
+            Group::mul(&p, &u256_to_field::<Fr>(s)).into_affine().into()
+        );
+        let base: AffinePoint = p.into_affine().into();
+
+        let raw: AffinePoint = contract
+            .affine_scalar_mul_raw(base.clone(), s)
+            .call()
+            .await?
+            .into();
+        assert_eq!(raw, expected, "raw policy, scalar {}", s);
+        let reduced: AffinePoint = contract
+            .affine_scalar_mul_reduce(base.clone(), s)
+            .call()
+            .await?
+            .into();
+        assert_eq!(reduced, expected, "reduce policy, scalar {}", s);
+        if s < r {
+            let strict: AffinePoint = contract
+                .affine_scalar_mul_strict(base, s)
+                .call()
+                .await?
+                .into();
+            assert_eq!(strict, expected, "strict policy, scalar {}", s);
+        } else {
+            contract
+                .affine_scalar_mul_strict(base, s)
+                .call()
+                .await
+                .should_revert_with_message("Pallas: invalid scalar field");
+        }
+    }
+
+    // MSM with a valid scalar and a scalar at the boundary
+    for &s in scalars.iter() {
+        let bases: Vec<Affine> = (0..2).map(|_| Projective::rand(rng).into()).collect();
+        let valid = Fr::rand(rng);
+        let expected: AffinePoint = (Group::mul(&bases[0].into_projective(), &valid)
+            + ProjectiveCurve::mul(bases[1].into_projective(), s.0))
+        .into_affine()
+        .into();
+        let bases: Vec<AffinePoint> = bases.into_iter().map(|b| b.into()).collect();
+        let msm_scalars = vec![field_to_u256(valid), s];
+
+        let raw: AffinePoint = contract
+            .multi_scalar_mul_raw(bases.clone(), msm_scalars.clone())
+            .call()
+            .await?
+            .into();
+        assert_eq!(raw, expected, "raw MSM, scalar {}", s);
+        let reduced: AffinePoint = contract
+            .multi_scalar_mul_reduce(bases.clone(), msm_scalars.clone())
+            .call()
+            .await?
+            .into();
+        assert_eq!(reduced, expected, "reduce MSM, scalar {}", s);
+        if s < r {
+            let strict: AffinePoint = contract
+                .multi_scalar_mul_strict(bases, msm_scalars)
+                .call()
+                .await?
+                .into();
+            assert_eq!(strict, expected, "strict MSM, scalar {}", s);
+        } else {
+            contract
+                .multi_scalar_mul_strict(bases, msm_scalars)
+                .call()
+                .await
+                .should_revert_with_message("Pallas: invalid scalar field");
+        }
+    }
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_is_y_negative() -> Result<()> {
     let rng = &mut ark_std::test_rng();
@@ -354,6 +658,58 @@ async fn test_is_y_negative() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_y_sign_conventions() -> Result<()> {
+    let rng = &mut ark_std::test_rng();
+    let contract = deploy_contract().await?;
+
+    // y of random points, and around (p - 1) / 2 (with a random x, the sign
+    // functions don't check that the point is on the curve)
+    let half = Fq::from_repr(<Fq as PrimeField>::Params::MODULUS_MINUS_ONE_DIV_TWO).unwrap();
+    let mut points: Vec<(Fq, Fq)> = (0..20)
+        .map(|_| {
+            let p: Affine = Projective::rand(rng).into();
+            (p.x, p.y)
+        })
+        .collect();
+    for y in [
+        half - Fq::one(),
+        half,
+        half + Fq::one(),
+        Fq::one(),
+        -Fq::one(),
+    ] {
+        points.push((Fq::rand(rng), y));
+    }
+
+    let client = get_funded_deployer().await?;
+    let mut negative = Batch::new(client.clone());
+    let mut greatest = Batch::new(client.clone());
+    let mut even = Batch::new(client.clone());
+    let mut parity = Batch::new(client);
+    for &(x, y) in points.iter() {
+        let point = AffinePoint {
+            x: field_to_u256(x),
+            y: field_to_u256(y),
+        };
+        negative.add(contract.is_y_negative(point.clone()));
+        greatest.add(contract.is_y_greatest(point.clone()));
+        even.add(contract.is_y_even(point.clone()));
+        parity.add(contract.y_parity(point));
+    }
+    let negative = negative.call_all().await?;
+    let greatest = greatest.call_all().await?;
+    let even = even.call_all().await?;
+    let parity = parity.call_all().await?;
+    for (i, &(_, y)) in points.iter().enumerate() {
+        assert_eq!(negative[i], is_y_negative(y), "isYNegative of {}", y);
+        assert_eq!(greatest[i], is_y_greatest(y), "isYGreatest of {}", y);
+        assert_eq!(even[i], is_y_even(y), "isYEven of {}", y);
+        assert_eq!(parity[i], y_parity(y), "yParity of {}", y);
+    }
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_invert() -> Result<()> {
     let rng = &mut ark_std::test_rng();
@@ -376,54 +732,353 @@ async fn test_invert() -> Result<()> {
 }
 
 #[tokio::test]
-async fn test_validate_curve_point() -> Result<()> {
+async fn test_hints() -> Result<()> {
     let rng = &mut ark_std::test_rng();
     let contract = deploy_contract().await?;
-    let p: Affine = Projective::rand(rng).into();
-
-    println!(
-        "gas cost: is_on_curve: {}",
+    type Parameters = ark_pallas::PallasParameters;
+
+    // inverse, checked with a single mulmod
+    let f = Fq::rand(rng);
+    let inverse = hints::inverse_hint(f).unwrap();
+    gas::measure(
+        "TestPallas.invertFqWithHint",
+        1,
+        contract.invert_fq_with_hint(field_to_u256(f), inverse),
+    )
+    .await?;
+    assert_eq!(
         contract
-            .validate_curve_point(p.into())
-            .estimate_gas()
-            .await?
+            .invert_fq_with_hint(field_to_u256(f), inverse)
+            .call()
+            .await?,
+        field_to_u256(f.inverse().unwrap())
     );
-    contract.validate_curve_point(p.into()).call().await?;
+    contract
+        .invert_fq_with_hint(field_to_u256(f), inverse + U256::one())
+        .call()
+        .await
+        .should_revert_with_message("Pallas: invalid inverse hint");
+    contract
+        .invert_fq_with_hint(U256::zero(), U256::one())
+        .call()
+        .await
+        .should_revert_with_message("Pallas: invalid inverse hint");
+
+    // addition with the slope, including doubling and opposite points
+    let p1: Affine = Projective::rand(rng).into();
+    let p2: Affine = Projective::rand(rng).into();
+    gas::measure(
+        "TestPallas.affineAddWithHint",
+        1,
+        contract.affine_add_with_hint(p1.into(), p2.into(), hints::slope_hint(p1, p2)),
+    )
+    .await?;
+    for (p1, p2) in [(p1, p2), (p1, p1), (p1, -p1), (p1, Affine::zero())] {
+        let res: AffinePoint = contract
+            .affine_add_with_hint(p1.into(), p2.into(), hints::slope_hint(p1, p2))
+            .call()
+            .await?
+            .into();
+        assert_eq!(res, (p1 + p2).into());
+    }
+    for (p1, p2) in [(p1, p2), (p1, p1)] {
+        contract
+            .affine_add_with_hint(
+                p1.into(),
+                p2.into(),
+                hints::slope_hint(p1, p2) + U256::one(),
+            )
+            .call()
+            .await
+            .should_revert_with_message("Pallas: invalid slope hint");
+    }
 
-    async fn should_fail_validation(
-        contract: &TestPallas<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>,
-        bad_p: AffinePoint,
-    ) {
+    // GLV scalar multiplication, on random and edge scalars
+    let p: Affine = Projective::rand(rng).into();
+    let s = Fr::rand(rng);
+    let (k1, k2) = hints::glv_hint::<Parameters>(s);
+    gas::measure(
+        "TestPallas.affineScalarMulWithHint",
+        1,
+        contract.affine_scalar_mul_with_hint(p.into(), field_to_u256(s), k1, k2),
+    )
+    .await?
+    .should_cost_at_most(AFFINE_SCALAR_MUL_GAS);
+    let edge_cases = vec![Fr::zero(), Fr::from(1u64), -Fr::from(1u64)];
+    let random: Vec<Fr> = (0..10).map(|_| Fr::rand(rng)).collect();
+    for s in edge_cases.into_iter().chain(random) {
+        let p = Projective::rand(rng);
+        let (k1, k2) = hints::glv_hint::<Parameters>(s);
+        let res: AffinePoint = contract
+            .affine_scalar_mul_with_hint(p.into_affine().into(), field_to_u256(s), k1, k2)
+            .call()
+            .await?
+            .into();
+        assert_eq!(res, Group::mul(&p, &s).into_affine().into());
+    }
+    let one = I256::from_raw(U256::one());
+    contract
+        .affine_scalar_mul_with_hint(p.into(), field_to_u256(s), k1 + one, k2)
+        .call()
+        .await
+        .should_revert_with_message("Pallas: invalid GLV hint");
+    // a decomposition out of bounds
+    contract
+        .affine_scalar_mul_with_hint(
+            p.into(),
+            field_to_u256(s),
+            I256::from_raw(field_to_u256(s)),
+            I256::zero(),
+        )
+        .call()
+        .await
+        .should_revert_with_message("Pallas: invalid GLV hint");
+
+    // decompression, with either square root as hint
+    gas::measure(
+        "TestPallas.decompressWithHint",
+        1,
+        contract.decompress_with_hint(
+            field_to_u256(p.x),
+            p.y > -p.y,
+            hints::sqrt_hint::<Parameters>(p.x).unwrap(),
+        ),
+    )
+    .await?;
+    for _ in 0..10 {
+        let p: Affine = Projective::rand(rng).into();
+        let greatest = p.y > -p.y;
+        let y = hints::sqrt_hint::<Parameters>(p.x).unwrap();
+        for y in [y, field_to_u256(-u256_to_field::<Fq>(y))] {
+            let res: AffinePoint = contract
+                .decompress_with_hint(field_to_u256(p.x), greatest, y)
+                .call()
+                .await?
+                .into();
+            assert_eq!(res, p.into());
+        }
         contract
-            .validate_curve_point(bad_p)
+            .decompress_with_hint(field_to_u256(p.x), greatest, y + U256::one())
             .call()
             .await
-            .should_revert_with_message("Pallas: invalid point");
+            .should_revert_with_message("Pallas: invalid square root hint");
     }
+    Ok(())
+}
 
-    // x = 0 should fail
-    let mut bad_p = p.clone();
-    bad_p.x = field_new!(Fq, "0");
-    should_fail_validation(&contract, bad_p.into()).await;
+#[ignore = "requires debug_traceCall, run with --include-ignored on geth"]
+#[tokio::test]
+async fn test_profile_invert() -> Result<()> {
+    let rng = &mut ark_std::test_rng();
+    let contract = deploy_contract().await?;
+    let call = contract.invert_fr(field_to_u256(Fr::rand(rng)));
+
+    let source_map =
+        profiler::SourceMap::from_build_output("contracts/mocks/TestPallas.sol", "TestPallas")?;
+    let profile =
+        profiler::profile("TestPallas", contract.client(), &call.tx, Some(&source_map)).await?;
+
+    assert_eq!(profile.by_precompile["0x05 (modexp)"].count, 1);
+    assert!(profile.by_opcode["STATICCALL"].gas >= profile.by_precompile["0x05 (modexp)"].gas);
+    assert!(profile.folded_stacks().contains("0x05 (modexp)"));
+    if let Ok(dir) = std::env::var("PROFILE_DIR") {
+        profile.write_folded_stacks(&Path::new(&dir).join("invert_fr.folded"))?;
+    }
+    Ok(())
+}
 
-    // y = 0 should fail
-    let mut bad_p = p.clone();
-    bad_p.y = field_new!(Fq, "0");
-    should_fail_validation(&contract, bad_p.into()).await;
+#[tokio::test]
+async fn test_validate_curve_point() -> Result<()> {
+    let rng = &mut ark_std::test_rng();
+    let contract = deploy_contract().await?;
+    let p: Affine = Projective::rand(rng).into();
 
-    // x > p should fail
-    let mut bad_p: AffinePoint = p.clone().into();
-    bad_p.x = U256::MAX;
-    should_fail_validation(&contract, bad_p).await;
+    gas::measure(
+        "TestPallas.validateCurvePoint",
+        1,
+        contract.validate_curve_point(p.into()),
+    )
+    .await?;
+    contract.validate_curve_point(p.into()).call().await?;
+
+    async fn is_valid(
+        contract: &TestPallas<EthMiddleware>,
+        p: AffinePoint,
+        or_infinity: bool,
+    ) -> bool {
+        let result = if or_infinity {
+            contract.validate_curve_point_or_infinity(p).call().await
+        } else {
+            contract.validate_curve_point(p).call().await
+        };
+        match result {
+            Ok(()) => true,
+            result => {
+                result.should_revert_with_message("Pallas: invalid point");
+                false
+            }
+        }
+    }
+
+    // there is no point with x = 0 (5 is not a square) nor with y = 0 (the
+    // group has odd order, so no point of order 2)
+    assert!(Affine::get_point_from_x(Fq::zero(), false).is_none());
+    assert!(Affine::get_point_from_x(Fq::zero(), true).is_none());
+    assert_eq!(Fr::characteristic()[0] & 1, 1);
+
+    // the point of infinity is not on the curve for `validateCurvePoint`
+    let infinity = AffinePoint {
+        x: U256::zero(),
+        y: U256::zero(),
+    };
+    assert!(!is_valid(&contract, infinity.clone(), false).await);
+    assert!(is_valid(&contract, infinity, true).await);
+
+    // otherwise the library agrees with arkworks on random points and points
+    // with a zero coordinate, `validateCurvePointOrInfinity` also on the point
+    // of infinity
+    let mut candidates = vec![
+        p,
+        -p,
+        Affine::zero(),
+        Affine::new(Fq::zero(), Fq::one(), false),
+        Affine::new(field_new!(Fq, "1"), field_new!(Fq, "3"), false),
+    ];
+    for _ in 0..5 {
+        candidates.push(Affine::new(Fq::zero(), Fq::rand(rng), false));
+        candidates.push(Affine::new(Fq::rand(rng), Fq::zero(), false));
+    }
+    for candidate in candidates {
+        assert_eq!(
+            is_valid(&contract, candidate.into(), false).await,
+            candidate.is_on_curve() && !candidate.is_zero(),
+            "{:?}",
+            candidate
+        );
+        assert_eq!(
+            is_valid(&contract, candidate.into(), true).await,
+            candidate.is_on_curve(),
+            "{:?}",
+            candidate
+        );
+    }
 
-    // y > p should fail
+    // x >= p and y >= p should fail
+    let mut bad_p: AffinePoint = p.clone().into();
+    bad_p.x = bad_p.x + field_to_u256(-Fq::one()) + U256::one();
+    assert!(!is_valid(&contract, bad_p.clone(), false).await);
+    assert!(!is_valid(&contract, bad_p, true).await);
     let mut bad_p: AffinePoint = p.clone().into();
     bad_p.y = U256::MAX;
-    should_fail_validation(&contract, bad_p).await;
+    assert!(!is_valid(&contract, bad_p.clone(), false).await);
+    assert!(!is_valid(&contract, bad_p, true).await);
 
-    // not on curve point (y^2 = x^3 + 5 mod p) should fail
-    let bad_p = Affine::new(field_new!(Fq, "1"), field_new!(Fq, "3"), false);
-    should_fail_validation(&contract, bad_p.into()).await;
+    // `validateNonInfinity` rejects the point of infinity only
+    contract.validate_non_infinity(p.into()).call().await?;
+    contract
+        .validate_non_infinity(Affine::zero().into())
+        .call()
+        .await
+        .should_revert_with_message("Pallas: point of infinity");
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_validate_projective_curve_point() -> Result<()> {
+    let rng = &mut ark_std::test_rng();
+    let contract = deploy_contract().await?;
+
+    async fn is_valid(
+        contract: &TestPallas<EthMiddleware>,
+        p: (Fq, Fq, Fq),
+        or_infinity: bool,
+    ) -> bool {
+        let point = ProjectivePoint {
+            x: field_to_u256(p.0),
+            y: field_to_u256(p.1),
+            z: field_to_u256(p.2),
+        };
+        let result = if or_infinity {
+            contract
+                .validate_projective_curve_point_or_infinity(point)
+                .call()
+                .await
+        } else {
+            contract.validate_projective_curve_point(point).call().await
+        };
+        match result {
+            Ok(()) => true,
+            result => {
+                result.should_revert_with_message("Pallas: invalid point");
+                false
+            }
+        }
+    }
+
+    // on the curve if (x/z^2, y/z^3) is, and (0, 0, 0) is the point of
+    // infinity, only accepted by `validateCurvePointOrInfinity`
+    fn is_on_curve(p: (Fq, Fq, Fq), or_infinity: bool) -> bool {
+        match p.2.inverse() {
+            Some(z_inv) => {
+                let z_inv2 = z_inv.square();
+                Affine::new(p.0 * z_inv2, p.1 * z_inv2 * z_inv, false).is_on_curve()
+            }
+            None => or_infinity && p.0.is_zero() && p.1.is_zero(),
+        }
+    }
+
+    let q = Projective::rand(rng);
+    gas::measure(
+        "TestPallas.validateProjectiveCurvePoint",
+        1,
+        contract.validate_projective_curve_point(q.into()),
+    )
+    .await?;
+
+    let t = Fq::rand(rng);
+    let mut candidates = vec![
+        (q.x, q.y, q.z),
+        // the same point with another z
+        (q.x * t.square(), q.y * t.square() * t, q.z * t),
+        (q.x, q.y, q.z + Fq::one()),
+        (q.x, -q.y, q.z),
+        (Fq::zero(), Fq::zero(), Fq::zero()),
+        (Fq::one(), Fq::one(), Fq::zero()),
+        (t.square(), t.square() * t, Fq::zero()),
+    ];
+    for _ in 0..5 {
+        candidates.push((Fq::zero(), Fq::rand(rng), Fq::rand(rng)));
+        candidates.push((Fq::rand(rng), Fq::zero(), Fq::rand(rng)));
+    }
+    for candidate in candidates {
+        for or_infinity in [false, true] {
+            assert_eq!(
+                is_valid(&contract, candidate, or_infinity).await,
+                is_on_curve(candidate, or_infinity),
+                "{:?}",
+                candidate
+            );
+        }
+    }
+
+    // z >= p should fail
+    let mut bad_q: ProjectivePoint = q.into();
+    bad_q.z = U256::MAX;
+    contract
+        .validate_projective_curve_point(bad_q)
+        .call()
+        .await
+        .should_revert_with_message("Pallas: invalid point");
+
+    contract
+        .validate_projective_non_infinity(q.into())
+        .call()
+        .await?;
+    contract
+        .validate_projective_non_infinity(Projective::zero().into())
+        .call()
+        .await
+        .should_revert_with_message("Pallas: point of infinity");
     Ok(())
 }
 
@@ -511,17 +1166,18 @@ async fn test_doubling() -> Result<()> {
     let contract = deploy_contract().await?;
 
     let p = Projective::rand(rng);
-    println!(
-        "gas cost: affine doubling: {}",
-        contract
-            .affine_double(p.into_affine().into())
-            .estimate_gas()
-            .await?
-    );
-    println!(
-        "gas cost: projective doubling: {}",
-        contract.projective_double(p.into()).estimate_gas().await?
-    );
+    gas::measure(
+        "TestPallas.affineDouble",
+        1,
+        contract.affine_double(p.into_affine().into()),
+    )
+    .await?;
+    gas::measure(
+        "TestPallas.projectiveDouble",
+        1,
+        contract.projective_double(p.into()),
+    )
+    .await?;
 
     for _ in 0..10 {
         let p = Projective::rand(rng);
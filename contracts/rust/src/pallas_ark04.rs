@@ -0,0 +1,240 @@
+//! The conversion and arithmetic tests of `pallas`, on the arkworks 0.4 types,
+//! so that a build with `ark-0-4` only still tests the curve.
+#![cfg(all(test, feature = "ark-0-4"))]
+
+use crate::{
+    deployments::deploy_or_reuse,
+    ethereum::{artifact_path, get_funded_deployer, EthMiddleware},
+    multicall::Batch,
+    types::{
+        ark04::{field_to_u256, u256_to_field},
+        PallasAffinePoint as AffinePoint, PallasProjectivePoint as ProjectivePoint, TestPallas,
+    },
+};
+use anyhow::Result;
+use ark_ec_04::{AffineRepr, CurveGroup, Group, VariableBaseMSM};
+use ark_ff_04::{Field, One, Zero};
+use ark_pallas_04::{Affine, Fq, Fr, Projective};
+use ark_std_04::UniformRand;
+use ethers::prelude::*;
+
+async fn deploy_contract() -> Result<TestPallas<EthMiddleware>> {
+    let client = get_funded_deployer().await?;
+    let contract = deploy_or_reuse(
+        client.clone(),
+        &artifact_path("contracts/mocks/TestPallas.sol:TestPallas")?,
+        (),
+    )
+    .await?;
+    Ok(TestPallas::new(contract.address(), client))
+}
+
+#[test]
+fn test_field_conversion() {
+    let rng = &mut ark_std_04::test_rng();
+    for _ in 0..10 {
+        let f = Fq::rand(rng);
+        assert_eq!(f, u256_to_field(field_to_u256(f)));
+        let f = Fr::rand(rng);
+        assert_eq!(f, u256_to_field(field_to_u256(f)));
+    }
+    assert_eq!(field_to_u256(Fq::zero()), U256::zero());
+    assert_eq!(field_to_u256(Fr::one()), U256::one());
+}
+
+#[test]
+fn test_point_conversion() {
+    let rng = &mut ark_std_04::test_rng();
+
+    let zero: AffinePoint = Affine::identity().into();
+    assert_eq!((zero.x, zero.y), (U256::zero(), U256::zero()));
+    assert!(Affine::from(zero).is_zero());
+    let zero: ProjectivePoint = Projective::zero().into();
+    assert_eq!(
+        (zero.x, zero.y, zero.z),
+        (U256::zero(), U256::zero(), U256::zero())
+    );
+    assert!(Projective::from(zero).is_zero());
+
+    for _ in 0..10 {
+        let p = Projective::rand(rng);
+        assert_eq!(Projective::from(ProjectivePoint::from(p)), p);
+        let p = p.into_affine();
+        assert_eq!(Affine::from(AffinePoint::from(p)), p);
+    }
+}
+
+#[tokio::test]
+async fn test_group_generators() -> Result<()> {
+    let contract = deploy_contract().await?;
+
+    let gen: AffinePoint = contract.affine_generator().call().await?;
+    assert_eq!(Affine::from(gen), Affine::generator());
+    let gen: ProjectivePoint = contract.projective_generator().call().await?;
+    assert_eq!(Projective::from(gen), Projective::generator());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_to_projective() -> Result<()> {
+    let rng = &mut ark_std_04::test_rng();
+    let contract = deploy_contract().await?;
+
+    for _ in 0..10 {
+        let p = Projective::rand(rng).into_affine();
+        let res: ProjectivePoint = contract.to_projective(p.into()).call().await?;
+        assert_eq!(Projective::from(res), Projective::from(p));
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_add_double_negate() -> Result<()> {
+    let rng = &mut ark_std_04::test_rng();
+    let client = get_funded_deployer().await?;
+    let contract = deploy_contract().await?;
+
+    let mut affine_batch = Batch::new(client.clone());
+    let mut affine_expected: Vec<Affine> = Vec::new();
+    let mut projective_batch = Batch::new(client);
+    let mut projective_expected: Vec<Projective> = Vec::new();
+    for _ in 0..20 {
+        let p1 = Projective::rand(rng);
+        let p2 = Projective::rand(rng);
+
+        affine_batch.add(contract.affine_add(p1.into_affine().into(), p2.into_affine().into()));
+        affine_expected.push((p1 + p2).into_affine());
+        affine_batch.add(contract.affine_double(p1.into_affine().into()));
+        affine_expected.push(p1.double().into_affine());
+        affine_batch.add(contract.affine_negate(p1.into_affine().into()));
+        affine_expected.push(-p1.into_affine());
+
+        projective_batch.add(contract.projective_add(p1.into(), p2.into()));
+        projective_expected.push(p1 + p2);
+        projective_batch.add(contract.projective_double(p1.into()));
+        projective_expected.push(p1.double());
+        projective_batch.add(contract.projective_negate(p1.into()));
+        projective_expected.push(-p1);
+    }
+    for (i, (res, expected)) in affine_batch
+        .call_all()
+        .await?
+        .into_iter()
+        .zip(affine_expected)
+        .enumerate()
+    {
+        assert_eq!(Affine::from(res), expected, "affine vector {}", i);
+    }
+    for (i, (res, expected)) in projective_batch
+        .call_all()
+        .await?
+        .into_iter()
+        .zip(projective_expected)
+        .enumerate()
+    {
+        assert_eq!(Projective::from(res), expected, "projective vector {}", i);
+    }
+
+    // O_E + P = P
+    let p = Projective::rand(rng).into_affine();
+    let res = contract
+        .affine_add(p.into(), Affine::identity().into())
+        .call()
+        .await?;
+    assert_eq!(Affine::from(res), p);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_scalar_mul() -> Result<()> {
+    let rng = &mut ark_std_04::test_rng();
+    let client = get_funded_deployer().await?;
+    let contract = deploy_contract().await?;
+
+    let mut affine_batch = Batch::new(client.clone()).chunk_size(10);
+    let mut affine_expected: Vec<Affine> = Vec::new();
+    let mut projective_batch = Batch::new(client).chunk_size(10);
+    let mut projective_expected: Vec<Projective> = Vec::new();
+    for i in 0..20 {
+        let p = Projective::rand(rng);
+        // small scalars as edge cases
+        let s = if i < 3 {
+            Fr::from(i as u64 + 1)
+        } else {
+            Fr::rand(rng)
+        };
+        affine_batch.add(contract.affine_scalar_mul(p.into_affine().into(), field_to_u256(s)));
+        affine_expected.push((p * s).into_affine());
+        projective_batch.add(contract.projective_scalar_mul(p.into(), field_to_u256(s)));
+        projective_expected.push(p * s);
+    }
+    for (i, (res, expected)) in affine_batch
+        .call_all()
+        .await?
+        .into_iter()
+        .zip(affine_expected)
+        .enumerate()
+    {
+        assert_eq!(Affine::from(res), expected, "affine vector {}", i);
+    }
+    for (i, (res, expected)) in projective_batch
+        .call_all()
+        .await?
+        .into_iter()
+        .zip(projective_expected)
+        .enumerate()
+    {
+        assert_eq!(Projective::from(res), expected, "projective vector {}", i);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_multi_scalar_mul() -> Result<()> {
+    let rng = &mut ark_std_04::test_rng();
+    let contract = deploy_contract().await?;
+
+    for length in 1..5 {
+        let bases: Vec<Affine> = (0..length)
+            .map(|_| Projective::rand(rng).into_affine())
+            .collect();
+        let scalars: Vec<Fr> = (0..length).map(|_| Fr::rand(rng)).collect();
+
+        let res = contract
+            .test_multi_scalar_mul(
+                bases.iter().map(|&p| p.into()).collect(),
+                scalars.iter().map(|&s| field_to_u256(s)).collect(),
+            )
+            .call()
+            .await?;
+        let expected = Projective::msm(&bases, &scalars).unwrap().into_affine();
+        assert_eq!(Affine::from(res), expected);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_invert() -> Result<()> {
+    let rng = &mut ark_std_04::test_rng();
+    let contract = deploy_contract().await?;
+
+    for _ in 0..10 {
+        let f = Fr::rand(rng);
+        assert_eq!(
+            contract.invert_fr(field_to_u256(f)).call().await?,
+            field_to_u256(f.inverse().unwrap())
+        );
+
+        let f = Fq::rand(rng);
+        assert_eq!(
+            contract.invert_fq(field_to_u256(f)).call().await?,
+            field_to_u256(f.inverse().unwrap())
+        );
+    }
+    Ok(())
+}
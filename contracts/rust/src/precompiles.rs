@@ -0,0 +1,336 @@
+//! Precompiled contracts of the in-process EVM of `local_evm`: modexp (0x05),
+//! which the libraries use for inversions, and prototypes of native Pallas
+//! and Vesta operations, called by `PallasPrecompiles.sol` and
+//! `VestaPrecompiles.sol`.
+//!
+//! Points are encoded as in EIP-196, 32 byte big endian coordinates with
+//! (0, 0) as point of infinity. The prototypes are priced like the bn254
+//! precompiles after EIP-1108, without discount for MSM.
+use ark_ec::{
+    msm::VariableBaseMSM, short_weierstrass_jacobian::GroupAffine, AffineCurve, ProjectiveCurve,
+    SWModelParameters,
+};
+use ark_ff::{BigInteger, BigInteger256, PrimeField, Zero};
+use ethers::types::{H160, U256};
+use evm::{
+    executor::stack::{PrecompileFailure, PrecompileOutput, PrecompileResult, PrecompileSet},
+    Context, ExitError, ExitSucceed,
+};
+use num_bigint::BigUint;
+use std::borrow::Cow;
+
+pub const MODEXP: u64 = 0x05;
+pub const PALLAS_ADD: u64 = 0x100;
+pub const PALLAS_SCALAR_MUL: u64 = 0x101;
+pub const PALLAS_MULTI_SCALAR_MUL: u64 = 0x102;
+pub const VESTA_ADD: u64 = 0x110;
+pub const VESTA_SCALAR_MUL: u64 = 0x111;
+pub const VESTA_MULTI_SCALAR_MUL: u64 = 0x112;
+
+// also given as stipends by `PallasPrecompiles.sol` and `VestaPrecompiles.sol`
+pub const ADD_GAS: u64 = 150;
+pub const SCALAR_MUL_GAS: u64 = 6000;
+/// per term
+pub const MULTI_SCALAR_MUL_GAS: u64 = 6000;
+
+/// The precompiles of the in-process EVM. Without `pasta`, only modexp is
+/// available and the Solidity shims fall back to the libraries.
+#[derive(Clone, Copy, Debug)]
+pub struct PastaPrecompiles {
+    pub pasta: bool,
+}
+
+fn failure(reason: &'static str) -> PrecompileFailure {
+    PrecompileFailure::Error {
+        exit_status: ExitError::Other(Cow::Borrowed(reason)),
+    }
+}
+
+/// 32 byte word `index` of `input`, right padded with zeros as calldata.
+fn word(input: &[u8], index: usize) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    let start = (index * 32).min(input.len());
+    let end = (start + 32).min(input.len());
+    word[..end - start].copy_from_slice(&input[start..end]);
+    word
+}
+
+fn big_int(word: &[u8; 32]) -> BigInteger256 {
+    BigInteger256(U256::from_big_endian(word).0)
+}
+
+fn read_point<P: SWModelParameters>(
+    input: &[u8],
+    index: usize,
+) -> Result<GroupAffine<P>, PrecompileFailure>
+where
+    P::BaseField: PrimeField<BigInt = BigInteger256>,
+{
+    let x = big_int(&word(input, index));
+    let y = big_int(&word(input, index + 1));
+    if x.is_zero() && y.is_zero() {
+        return Ok(GroupAffine::zero());
+    }
+    let x = P::BaseField::from_repr(x).ok_or_else(|| failure("coordinate not in field"))?;
+    let y = P::BaseField::from_repr(y).ok_or_else(|| failure("coordinate not in field"))?;
+    // the cofactor of both curves is 1, no subgroup check needed
+    let p = GroupAffine::new(x, y, false);
+    if !p.is_on_curve() {
+        return Err(failure("point not on curve"));
+    }
+    Ok(p)
+}
+
+/// Scalar reduced modulo the group order.
+fn read_scalar<P: SWModelParameters>(input: &[u8], index: usize) -> BigInteger256
+where
+    P::ScalarField: PrimeField<BigInt = BigInteger256>,
+{
+    P::ScalarField::from_be_bytes_mod_order(&word(input, index)).into_repr()
+}
+
+fn write_point<P: SWModelParameters>(p: GroupAffine<P>) -> Vec<u8>
+where
+    P::BaseField: PrimeField<BigInt = BigInteger256>,
+{
+    let mut output = vec![0u8; 64];
+    if !p.is_zero() {
+        U256(p.x.into_repr().0).to_big_endian(&mut output[..32]);
+        U256(p.y.into_repr().0).to_big_endian(&mut output[32..]);
+    }
+    output
+}
+
+fn add<P: SWModelParameters>(input: &[u8]) -> Result<Vec<u8>, PrecompileFailure>
+where
+    P::BaseField: PrimeField<BigInt = BigInteger256>,
+{
+    let p1 = read_point::<P>(input, 0)?;
+    let p2 = read_point::<P>(input, 2)?;
+    Ok(write_point(p1 + p2))
+}
+
+fn scalar_mul<P: SWModelParameters>(input: &[u8]) -> Result<Vec<u8>, PrecompileFailure>
+where
+    P::BaseField: PrimeField<BigInt = BigInteger256>,
+    P::ScalarField: PrimeField<BigInt = BigInteger256>,
+{
+    let p = read_point::<P>(input, 0)?;
+    let s = read_scalar::<P>(input, 2);
+    Ok(write_point(p.mul(s).into_affine()))
+}
+
+fn multi_scalar_mul<P: SWModelParameters>(input: &[u8]) -> Result<Vec<u8>, PrecompileFailure>
+where
+    P::BaseField: PrimeField<BigInt = BigInteger256>,
+    P::ScalarField: PrimeField<BigInt = BigInteger256>,
+{
+    if input.len() % 96 != 0 {
+        return Err(failure("input is not a list of (x, y, s)"));
+    }
+    let terms = input.len() / 96;
+    let bases = (0..terms)
+        .map(|i| read_point::<P>(input, 3 * i))
+        .collect::<Result<Vec<_>, _>>()?;
+    let scalars: Vec<_> = (0..terms)
+        .map(|i| read_scalar::<P>(input, 3 * i + 2))
+        .collect();
+    Ok(write_point(
+        VariableBaseMSM::multi_scalar_mul(&bases, &scalars).into_affine(),
+    ))
+}
+
+/// Length of the word `index` of the modexp input, which must fit in memory.
+fn modexp_len(input: &[u8], index: usize) -> Result<usize, PrecompileFailure> {
+    let len = U256::from_big_endian(&word(input, index));
+    if len > U256::from(1024) {
+        return Err(failure("modexp input too large"));
+    }
+    Ok(len.as_usize())
+}
+
+/// `len` bytes of `input` from `start`, right padded with zeros.
+fn modexp_bytes(input: &[u8], start: usize, len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    if start < input.len() {
+        let end = (start + len).min(input.len());
+        bytes[..end - start].copy_from_slice(&input[start..end]);
+    }
+    bytes
+}
+
+/// Gas of modexp after EIP-2565.
+fn modexp_gas(input: &[u8]) -> Result<u64, PrecompileFailure> {
+    let (base_len, exp_len, mod_len) = (
+        modexp_len(input, 0)?,
+        modexp_len(input, 1)?,
+        modexp_len(input, 2)?,
+    );
+    let words = ((base_len.max(mod_len) + 7) / 8) as u64;
+    let complexity = words * words;
+
+    // only the first 32 bytes of the exponent count
+    let exp_head = BigUint::from_bytes_be(&modexp_bytes(input, 96 + base_len, exp_len.min(32)));
+    let head_bits = exp_head.bits().saturating_sub(1);
+    let iterations = if exp_len <= 32 {
+        head_bits
+    } else {
+        8 * (exp_len as u64 - 32) + head_bits
+    }
+    .max(1);
+    Ok((complexity * iterations / 3).max(200))
+}
+
+fn modexp(input: &[u8]) -> Result<Vec<u8>, PrecompileFailure> {
+    let (base_len, exp_len, mod_len) = (
+        modexp_len(input, 0)?,
+        modexp_len(input, 1)?,
+        modexp_len(input, 2)?,
+    );
+    let base = BigUint::from_bytes_be(&modexp_bytes(input, 96, base_len));
+    let exp = BigUint::from_bytes_be(&modexp_bytes(input, 96 + base_len, exp_len));
+    let modulus = BigUint::from_bytes_be(&modexp_bytes(input, 96 + base_len + exp_len, mod_len));
+
+    let mut output = vec![0u8; mod_len];
+    if modulus.bits() > 0 {
+        let result = base.modpow(&exp, &modulus).to_bytes_be();
+        output[mod_len - result.len()..].copy_from_slice(&result);
+    }
+    Ok(output)
+}
+
+/// Implementation of a precompile.
+type Run = fn(&[u8]) -> Result<Vec<u8>, PrecompileFailure>;
+
+impl PastaPrecompiles {
+    /// Gas cost and implementation of the precompile at `address`.
+    fn lookup(&self, address: H160, input: &[u8]) -> Option<(Result<u64, PrecompileFailure>, Run)> {
+        let terms = (input.len() / 96) as u64;
+        Some(match address.to_low_u64_be() {
+            _ if address.as_bytes()[..12] != [0u8; 12] => return None,
+            MODEXP => (modexp_gas(input), modexp as Run),
+            _ if !self.pasta => return None,
+            PALLAS_ADD => (Ok(ADD_GAS), add::<ark_pallas::PallasParameters> as Run),
+            PALLAS_SCALAR_MUL => (
+                Ok(SCALAR_MUL_GAS),
+                scalar_mul::<ark_pallas::PallasParameters> as Run,
+            ),
+            PALLAS_MULTI_SCALAR_MUL => (
+                Ok(MULTI_SCALAR_MUL_GAS * terms),
+                multi_scalar_mul::<ark_pallas::PallasParameters> as Run,
+            ),
+            VESTA_ADD => (Ok(ADD_GAS), add::<ark_vesta::VestaParameters> as Run),
+            VESTA_SCALAR_MUL => (
+                Ok(SCALAR_MUL_GAS),
+                scalar_mul::<ark_vesta::VestaParameters> as Run,
+            ),
+            VESTA_MULTI_SCALAR_MUL => (
+                Ok(MULTI_SCALAR_MUL_GAS * terms),
+                multi_scalar_mul::<ark_vesta::VestaParameters> as Run,
+            ),
+            _ => return None,
+        })
+    }
+}
+
+impl PrecompileSet for PastaPrecompiles {
+    fn execute(
+        &self,
+        address: H160,
+        input: &[u8],
+        gas_limit: Option<u64>,
+        _context: &Context,
+        _is_static: bool,
+    ) -> Option<PrecompileResult> {
+        let (cost, run) = self.lookup(address, input)?;
+        Some(cost.and_then(|cost| {
+            if gas_limit.map_or(false, |limit| cost > limit) {
+                return Err(PrecompileFailure::Error {
+                    exit_status: ExitError::OutOfGas,
+                });
+            }
+            Ok(PrecompileOutput {
+                exit_status: ExitSucceed::Returned,
+                cost,
+                output: run(input)?,
+                logs: vec![],
+            })
+        }))
+    }
+
+    fn is_precompile(&self, address: H160) -> bool {
+        self.lookup(address, &[]).is_some()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::field_to_u256;
+    use ark_ec::group::Group;
+    use ark_pallas::{Affine, Fr, PallasParameters, Projective};
+    use ark_std::UniformRand;
+
+    fn encode(words: &[U256]) -> Vec<u8> {
+        ethers::abi::encode(
+            &words
+                .iter()
+                .map(|w| ethers::abi::Token::Uint(*w))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    fn point(p: Affine) -> Vec<U256> {
+        vec![field_to_u256(p.x), field_to_u256(p.y)]
+    }
+
+    #[test]
+    fn test_modexp() {
+        // 3^5 mod 7 = 5
+        let input = encode(&[1.into(), 1.into(), 1.into(), 3.into(), 5.into(), 7.into()]);
+        let input = [&input[..96], &[3, 5, 7]].concat();
+        assert_eq!(modexp(&input).unwrap(), vec![5]);
+        assert_eq!(modexp_gas(&input).unwrap(), 200);
+        // zero modulus
+        let input = [&input[..96], &[3, 5, 0]].concat();
+        assert_eq!(modexp(&input).unwrap(), vec![0]);
+    }
+
+    #[test]
+    fn test_pasta_precompiles() {
+        let rng = &mut ark_std::test_rng();
+        let p1: Affine = Projective::rand(rng).into();
+        let p2: Affine = Projective::rand(rng).into();
+        let s = Fr::rand(rng);
+
+        let input = encode(&[point(p1), point(p2)].concat());
+        assert_eq!(
+            add::<PallasParameters>(&input).unwrap(),
+            encode(&point(p1 + p2))
+        );
+        let input = encode(&[point(p1), point(-p1)].concat());
+        assert_eq!(add::<PallasParameters>(&input).unwrap(), vec![0u8; 64]);
+
+        let input = encode(&[point(p1), vec![field_to_u256(s)]].concat());
+        assert_eq!(
+            scalar_mul::<PallasParameters>(&input).unwrap(),
+            encode(&point(Group::mul(&p1, &s)))
+        );
+
+        let input = encode(&[point(p1), vec![1.into()], point(p2), vec![2.into()]].concat());
+        assert_eq!(
+            multi_scalar_mul::<PallasParameters>(&input).unwrap(),
+            encode(&point(p1 + p2 + p2))
+        );
+
+        // not on curve
+        let input = encode(&[1.into(), 1.into(), 0.into(), 0.into()]);
+        assert!(add::<PallasParameters>(&input).is_err());
+
+        // only modexp without the prototypes
+        let precompiles = PastaPrecompiles { pasta: false };
+        assert!(precompiles.is_precompile(H160::from_low_u64_be(MODEXP)));
+        assert!(!precompiles.is_precompile(H160::from_low_u64_be(PALLAS_ADD)));
+        assert!(PastaPrecompiles { pasta: true }.is_precompile(H160::from_low_u64_be(VESTA_ADD)));
+    }
+}
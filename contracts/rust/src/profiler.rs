@@ -0,0 +1,565 @@
+//! Opcode-level gas profiler based on `debug_traceCall` struct logs.
+//!
+//! A traced call is aggregated by opcode, by precompile (e.g. the modexp at
+//! `0x05` used by `invert`) and, if a source map is provided, by internal
+//! Solidity function. The result can be exported in the folded-stack format
+//! understood by `flamegraph.pl` and `inferno-flamegraph`.
+use anyhow::{anyhow, Result};
+use ethers::{
+    prelude::{Middleware, U256},
+    types::transaction::eip2718::TypedTransaction,
+};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fmt, fs, path::Path};
+
+/// One step of a geth struct log trace.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLog {
+    pub pc: usize,
+    pub op: String,
+    pub gas: u64,
+    pub gas_cost: u64,
+    pub depth: u64,
+    #[serde(default)]
+    pub stack: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TraceResult {
+    gas: u64,
+    failed: bool,
+    struct_logs: Vec<StructLog>,
+}
+
+/// Trace `tx` with `debug_traceCall` and return the total gas of the call
+/// together with its struct logs.
+pub async fn trace_call<M: Middleware>(
+    client: &M,
+    tx: &TypedTransaction,
+) -> Result<(u64, Vec<StructLog>)> {
+    let options = serde_json::json!({
+        "disableStorage": true,
+        "disableMemory": true,
+        "disableStack": false,
+    });
+    let trace: TraceResult = client
+        .provider()
+        .request("debug_traceCall", (tx, "latest", options))
+        .await?;
+    if trace.failed {
+        return Err(anyhow!("traced call reverted"));
+    }
+    Ok((trace.gas, trace.struct_logs))
+}
+
+/// Gas and number of executions of an opcode or a precompile.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub count: u64,
+    pub gas: u64,
+}
+
+impl Stats {
+    fn add(&mut self, gas: u64) {
+        self.count += 1;
+        self.gas += gas;
+    }
+}
+
+/// Name of the precompile at `address`, if any.
+pub fn precompile_name(address: U256) -> Option<&'static str> {
+    if address > U256::from(9) {
+        return None;
+    }
+    Some(match address.as_u64() {
+        1 => "ecrecover",
+        2 => "sha256",
+        3 => "ripemd160",
+        4 => "identity",
+        5 => "modexp",
+        6 => "bn256Add",
+        7 => "bn256ScalarMul",
+        8 => "bn256Pairing",
+        9 => "blake2f",
+        _ => return None,
+    })
+}
+
+fn parse_stack_word(word: &str) -> Result<U256> {
+    Ok(U256::from_str_radix(word.trim_start_matches("0x"), 16)?)
+}
+
+fn is_call(op: &str) -> bool {
+    matches!(op, "CALL" | "CALLCODE" | "DELEGATECALL" | "STATICCALL")
+}
+
+/// Gas spent by each step itself, excluding the gas of nested call frames.
+fn exclusive_costs(logs: &[StructLog]) -> Vec<u64> {
+    let mut costs = Vec::with_capacity(logs.len());
+    for (i, log) in logs.iter().enumerate() {
+        // the next step in the same frame, skipping any nested frame
+        let next = logs[i + 1..]
+            .iter()
+            .position(|l| l.depth <= log.depth)
+            .map(|j| i + 1 + j);
+        let cost = match next {
+            Some(j) if logs[j].depth == log.depth => {
+                let total = log.gas.saturating_sub(logs[j].gas);
+                if j == i + 1 {
+                    total
+                } else {
+                    // subtract the gas used within the nested frame
+                    let first = &logs[i + 1];
+                    let last = &logs[j - 1];
+                    let inner = first.gas.saturating_sub(last.gas) + last.gas_cost;
+                    total.saturating_sub(inner)
+                }
+            }
+            _ => log.gas_cost,
+        };
+        costs.push(cost);
+    }
+    costs
+}
+
+/// Aggregated gas profile of a traced call.
+#[derive(Clone, Debug, Default)]
+pub struct Profile {
+    /// total gas of the call, including intrinsic gas
+    pub total_gas: u64,
+    pub by_opcode: BTreeMap<String, Stats>,
+    /// keyed by the precompile name, e.g. `0x05 (modexp)`
+    pub by_precompile: BTreeMap<String, Stats>,
+    /// exclusive gas of each internal function, keyed by its name
+    pub by_function: BTreeMap<String, Stats>,
+    /// folded stacks `frame;frame;...;leaf` with their exclusive gas
+    pub folded: BTreeMap<String, u64>,
+}
+
+impl Profile {
+    /// Aggregate struct logs. `source_map` attributes gas to the internal
+    /// functions of the (outermost) traced contract named `root`.
+    pub fn from_struct_logs(
+        root: &str,
+        total_gas: u64,
+        logs: &[StructLog],
+        source_map: Option<&SourceMap>,
+    ) -> Result<Self> {
+        let mut profile = Profile {
+            total_gas,
+            ..Default::default()
+        };
+        let costs = exclusive_costs(logs);
+        let mut functions: Vec<String> = vec![];
+
+        for (i, (log, &cost)) in logs.iter().zip(costs.iter()).enumerate() {
+            let mut leaf = log.op.clone();
+
+            if is_call(&log.op) && logs.get(i + 1).map(|l| l.depth) == Some(log.depth) {
+                let stack = log
+                    .stack
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("struct logs have no stack, can't resolve calls"))?;
+                let address = parse_stack_word(&stack[stack.len() - 2])?;
+                if let Some(name) = precompile_name(address) {
+                    leaf = format!("0x{:02x} ({})", address.as_u64(), name);
+                    profile
+                        .by_precompile
+                        .entry(leaf.clone())
+                        .or_default()
+                        .add(cost);
+                }
+            }
+            profile
+                .by_opcode
+                .entry(log.op.clone())
+                .or_default()
+                .add(cost);
+
+            let mut frames = vec![root.to_string()];
+            if log.depth == 1 {
+                if let Some(function) = functions.last() {
+                    profile
+                        .by_function
+                        .entry(function.clone())
+                        .or_default()
+                        .add(cost);
+                }
+                frames.extend(functions.iter().cloned());
+            } else {
+                frames.push(format!("<call depth {}>", log.depth));
+            }
+            frames.push(leaf);
+            *profile.folded.entry(frames.join(";")).or_default() += cost;
+
+            // update the internal call stack after attributing this step
+            if let (Some(map), 1) = (source_map, log.depth) {
+                match map.jump_at(log.pc) {
+                    Some(Jump::Into) => {
+                        let target = logs.get(i + 1).map(|l| l.pc).unwrap_or_default();
+                        functions.push(map.function_name_at(target));
+                    }
+                    Some(Jump::Out) => {
+                        functions.pop();
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        Ok(profile)
+    }
+
+    /// The profile in folded-stack format, one `stack value` pair per line.
+    pub fn folded_stacks(&self) -> String {
+        self.folded
+            .iter()
+            .filter(|(_, gas)| **gas > 0)
+            .map(|(stack, gas)| format!("{} {}\n", stack, gas))
+            .collect()
+    }
+
+    pub fn write_folded_stacks(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.folded_stacks())?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn sorted(stats: &BTreeMap<String, Stats>) -> Vec<(&String, &Stats)> {
+            let mut v: Vec<_> = stats.iter().collect();
+            v.sort_by(|a, b| b.1.gas.cmp(&a.1.gas));
+            v
+        }
+
+        writeln!(f, "total gas: {}", self.total_gas)?;
+        for (title, stats) in [
+            ("opcode", &self.by_opcode),
+            ("precompile", &self.by_precompile),
+            ("function", &self.by_function),
+        ] {
+            if stats.is_empty() {
+                continue;
+            }
+            writeln!(f, "{:<32} {:>10} {:>12}", title, "count", "gas")?;
+            for (name, s) in sorted(stats) {
+                writeln!(f, "{:<32} {:>10} {:>12}", name, s.count, s.gas)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Trace `tx` and aggregate its gas usage, see `Profile::from_struct_logs`.
+pub async fn profile<M: Middleware>(
+    root: &str,
+    client: &M,
+    tx: &TypedTransaction,
+    source_map: Option<&SourceMap>,
+) -> Result<Profile> {
+    let (total_gas, logs) = trace_call(client, tx).await?;
+    Profile::from_struct_logs(root, total_gas, &logs, source_map)
+}
+
+/// Jump type of an instruction in a solc source map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Jump {
+    Into,
+    Out,
+    Regular,
+}
+
+/// One decompressed entry of a solc source map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourceMapEntry {
+    pub offset: usize,
+    pub length: usize,
+    /// source index, -1 for compiler generated code
+    pub file: i64,
+    pub jump: Jump,
+}
+
+/// Decompress a solc source map `s:l:f:j[:m];...`, where empty fields take
+/// the value of the previous entry.
+pub fn parse_source_map(source_map: &str) -> Result<Vec<SourceMapEntry>> {
+    let mut entries = vec![];
+    let mut prev = SourceMapEntry {
+        offset: 0,
+        length: 0,
+        file: -1,
+        jump: Jump::Regular,
+    };
+
+    for item in source_map.split(';') {
+        let mut entry = prev;
+        for (i, field) in item.split(':').enumerate() {
+            if field.is_empty() {
+                continue;
+            }
+            match i {
+                0 => entry.offset = field.parse()?,
+                1 => entry.length = field.parse()?,
+                2 => entry.file = field.parse()?,
+                3 => {
+                    entry.jump = match field {
+                        "i" => Jump::Into,
+                        "o" => Jump::Out,
+                        "-" => Jump::Regular,
+                        _ => return Err(anyhow!("invalid jump type {}", field)),
+                    }
+                }
+                _ => (),
+            }
+        }
+        entries.push(entry);
+        prev = entry;
+    }
+    Ok(entries)
+}
+
+/// Map each program counter of `bytecode` to its instruction index.
+pub fn instruction_indices(bytecode: &[u8]) -> BTreeMap<usize, usize> {
+    let mut indices = BTreeMap::new();
+    let mut pc = 0;
+    let mut index = 0;
+    while pc < bytecode.len() {
+        indices.insert(pc, index);
+        let op = bytecode[pc];
+        // PUSH1 ..= PUSH32 carry 1 ..= 32 bytes of immediate data
+        pc += if (0x60..=0x7f).contains(&op) {
+            (op - 0x5f) as usize + 1
+        } else {
+            1
+        };
+        index += 1;
+    }
+    indices
+}
+
+/// Source map of the deployed bytecode of a contract, together with the
+/// sources it refers to.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    entries: Vec<SourceMapEntry>,
+    indices: BTreeMap<usize, usize>,
+    /// source contents, indexed by source id
+    sources: BTreeMap<i64, String>,
+}
+
+impl SourceMap {
+    pub fn new(
+        source_map: &str,
+        deployed_bytecode: &[u8],
+        sources: BTreeMap<i64, String>,
+    ) -> Result<Self> {
+        Ok(Self {
+            entries: parse_source_map(source_map)?,
+            indices: instruction_indices(deployed_bytecode),
+            sources,
+        })
+    }
+
+    /// Load the source map of `contract` (e.g. `TestPallas`) defined in
+    /// `file` (e.g. `contracts/mocks/TestPallas.sol`) from the hardhat
+    /// `build-info` files in `artifacts_dir`.
+    pub fn from_hardhat_build_info(
+        artifacts_dir: &Path,
+        file: &str,
+        contract: &str,
+    ) -> Result<Self> {
+        for build_info in fs::read_dir(artifacts_dir.join("build-info"))? {
+            let build_info: serde_json::Value =
+                serde_json::from_reader(fs::File::open(build_info?.path())?)?;
+            let evm = &build_info["output"]["contracts"][file][contract]["evm"];
+            let (source_map, object) = match (
+                evm["deployedBytecode"]["sourceMap"].as_str(),
+                evm["deployedBytecode"]["object"].as_str(),
+            ) {
+                (Some(s), Some(o)) => (s, o),
+                _ => continue,
+            };
+
+            let mut sources = BTreeMap::new();
+            if let Some(output_sources) = build_info["output"]["sources"].as_object() {
+                for (name, source) in output_sources {
+                    if let (Some(id), Some(content)) = (
+                        source["id"].as_i64(),
+                        build_info["input"]["sources"][name]["content"].as_str(),
+                    ) {
+                        sources.insert(id, content.to_string());
+                    }
+                }
+            }
+            let bytecode = hex::decode(object.trim_start_matches("0x"))?;
+            return Self::new(source_map, &bytecode, sources);
+        }
+        Err(anyhow!("no build info found for {}:{}", file, contract))
+    }
+
+    /// Load the source map of `contract` defined in `file` from the solc
+    /// output written by `build.rs`.
+    pub fn from_build_output(file: &str, contract: &str) -> Result<Self> {
+        Self::from_hardhat_build_info(
+            Path::new(concat!(env!("OUT_DIR"), "/artifacts")),
+            file,
+            contract,
+        )
+    }
+
+    fn entry_at(&self, pc: usize) -> Option<&SourceMapEntry> {
+        self.indices.get(&pc).and_then(|&i| self.entries.get(i))
+    }
+
+    /// Jump type of the instruction at `pc`.
+    pub fn jump_at(&self, pc: usize) -> Option<Jump> {
+        self.entry_at(pc).map(|e| e.jump)
+    }
+
+    /// Name of the function whose entry point is at `pc`. The source range of
+    /// a function's entry `JUMPDEST` covers the whole function definition.
+    pub fn function_name_at(&self, pc: usize) -> String {
+        let entry = match self.entry_at(pc) {
+            Some(e) => e,
+            None => return format!("<pc {}>", pc),
+        };
+        let snippet = self
+            .sources
+            .get(&entry.file)
+            .and_then(|s| s.get(entry.offset..entry.offset + entry.length));
+        match snippet.and_then(|s| s.strip_prefix("function")) {
+            Some(rest) => rest
+                .trim_start()
+                .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            None => format!("<source {}:{}:{}>", entry.offset, entry.length, entry.file),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn step(pc: usize, op: &str, gas: u64, gas_cost: u64, depth: u64) -> StructLog {
+        StructLog {
+            pc,
+            op: op.to_string(),
+            gas,
+            gas_cost,
+            depth,
+            stack: None,
+        }
+    }
+
+    #[test]
+    fn source_map_decompression() {
+        let entries = parse_source_map("1:2:1;:9;2:1:2;;:::o;5::0:i").unwrap();
+        let expected = [
+            (1, 2, 1, Jump::Regular),
+            (1, 9, 1, Jump::Regular),
+            (2, 1, 2, Jump::Regular),
+            (2, 1, 2, Jump::Regular),
+            (2, 1, 2, Jump::Out),
+            (5, 1, 0, Jump::Into),
+        ];
+        assert_eq!(entries.len(), expected.len());
+        for (e, &(offset, length, file, jump)) in entries.iter().zip(expected.iter()) {
+            assert_eq!(
+                *e,
+                SourceMapEntry {
+                    offset,
+                    length,
+                    file,
+                    jump
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn pc_to_instruction_index() {
+        // PUSH1 0x80 PUSH2 0x0102 JUMPDEST PUSH32 <32 bytes> STOP
+        let mut bytecode = vec![0x60, 0x80, 0x61, 0x01, 0x02, 0x5b, 0x7f];
+        bytecode.extend_from_slice(&[0u8; 32]);
+        bytecode.push(0x00);
+        let indices = instruction_indices(&bytecode);
+        let expected: BTreeMap<usize, usize> = vec![(0, 0), (2, 1), (5, 2), (6, 3), (39, 4)]
+            .into_iter()
+            .collect();
+        assert_eq!(indices, expected);
+    }
+
+    #[test]
+    fn function_attribution() {
+        let source = "contract A { function foo() internal {} }".to_string();
+        let foo = source.find("function").unwrap();
+        let foo_len = "function foo() internal {}".len();
+        // instructions: JUMP (into), JUMPDEST (foo), JUMP (out), JUMPDEST, STOP
+        let source_map = format!(
+            "0:41:0:i;{f}:{l}:0:-;{f}:{l}:0:o;0:41:0:-;",
+            f = foo,
+            l = foo_len
+        );
+        let bytecode = [0x56, 0x5b, 0x56, 0x5b, 0x00];
+        let map = SourceMap::new(
+            &source_map,
+            &bytecode,
+            vec![(0, source)].into_iter().collect(),
+        )
+        .unwrap();
+        assert_eq!(map.jump_at(0), Some(Jump::Into));
+        assert_eq!(map.jump_at(2), Some(Jump::Out));
+        assert_eq!(map.function_name_at(1), "foo");
+
+        let logs = vec![
+            step(0, "JUMP", 100, 8, 1),
+            step(1, "JUMPDEST", 92, 1, 1),
+            step(2, "JUMP", 91, 8, 1),
+            step(3, "JUMPDEST", 83, 1, 1),
+            step(4, "STOP", 82, 0, 1),
+        ];
+        let profile = Profile::from_struct_logs("A", 21018, &logs, Some(&map)).unwrap();
+        assert_eq!(profile.by_function["foo"], Stats { count: 2, gas: 9 });
+        assert_eq!(profile.by_opcode["JUMP"], Stats { count: 2, gas: 16 });
+        assert_eq!(profile.folded["A;foo;JUMP"], 8);
+        assert_eq!(profile.folded["A;JUMP"], 8);
+        assert!(profile.folded_stacks().contains("A;foo;JUMPDEST 1\n"));
+    }
+
+    #[test]
+    fn precompile_and_nested_call_costs() {
+        let mut staticcall = step(1, "STATICCALL", 10_000, 9_000, 1);
+        staticcall.stack = Some(vec!["0x20".into(), "0x5".into(), "0x2328".into()]);
+        let mut call = step(4, "CALL", 8_695, 7_000, 1);
+        call.stack = Some(vec!["0x0".into(), "0xdead".into(), "0x1b58".into()]);
+        let logs = vec![
+            step(0, "PUSH1", 10_003, 3, 1),
+            staticcall,
+            // modexp cost 1200 + 100 for the call
+            step(2, "POP", 8_700, 2, 1),
+            step(3, "PUSH1", 8_698, 3, 1),
+            call,
+            step(0, "PUSH1", 6_000, 3, 2),
+            step(2, "STOP", 5_997, 0, 2),
+            step(4, "POP", 7_500, 2, 1),
+        ];
+        let costs = exclusive_costs(&logs);
+        assert_eq!(costs, vec![3, 1_300, 2, 3, 1_192, 3, 0, 2]);
+
+        let profile = Profile::from_struct_logs("A", 30_000, &logs, None).unwrap();
+        assert_eq!(
+            profile.by_precompile["0x05 (modexp)"],
+            Stats {
+                count: 1,
+                gas: 1_300
+            }
+        );
+        assert_eq!(profile.by_precompile.len(), 1);
+        assert_eq!(profile.folded["A;<call depth 2>;PUSH1"], 3);
+    }
+}
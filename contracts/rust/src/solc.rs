@@ -0,0 +1,63 @@
+//! Invocation of solc with a standard JSON input, shared by `build.rs` and
+//! `overrides::compile`.
+//!
+//! solc is taken from `SOLC_PATH` (set by the nix shell), or from the `PATH`.
+use serde_json::Value;
+use std::{
+    env,
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// Compile the standard JSON `input` and return the output of solc. With a
+/// `base_path`, imports which are not part of the input are read from it and
+/// from its `node_modules`. Fails if solc exits with an error, with its
+/// stderr, or reports compilation errors.
+pub fn compile_standard_json(input: &Value, base_path: Option<&Path>) -> Result<Value, String> {
+    let solc = env::var("SOLC_PATH").unwrap_or_else(|_| "solc".to_string());
+    let mut command = Command::new(&solc);
+    command.arg("--standard-json");
+    if let Some(root) = base_path {
+        command.arg("--base-path").arg(root);
+        if root.join("node_modules").is_dir() {
+            command.arg("--include-path").arg(root.join("node_modules"));
+        }
+    }
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Unable to run solc {:?}, set SOLC_PATH: {}", solc, e))?;
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.to_string().as_bytes())
+        .map_err(|e| e.to_string())?;
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    // compilation errors are reported in the output, a failure of solc itself
+    // (e.g. invalid arguments) only on stderr
+    if !output.status.success() {
+        return Err(format!(
+            "{} exited with {}:\n{}",
+            solc,
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    let output: Value = serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())?;
+
+    let errors: Vec<&str> = output["errors"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|e| e["severity"] == "error")
+        .filter_map(|e| e["formattedMessage"].as_str())
+        .collect();
+    if !errors.is_empty() {
+        return Err(format!("solc failed:\n{}", errors.join("\n")));
+    }
+    Ok(output)
+}
@@ -1,240 +1,162 @@
-use ark_ff::{to_bytes, PrimeField, Zero};
 use ethers::prelude::*;
-
-abigen!(
-    TestPallas,
-    "../abi/contracts/mocks/TestPallas.sol/TestPallas/abi.json",
-    event_derives(serde::Deserialize, serde::Serialize);
-
-    TestVesta,
-    "../abi/contracts/mocks/TestVesta.sol/TestVesta/abi.json",
-    event_derives(serde::Deserialize, serde::Serialize);
-
-    Greeter,
-    "../abi/contracts/Greeter.sol/Greeter/abi.json",
-    event_derives(serde::Deserialize, serde::Serialize);
-);
+#[cfg(feature = "pasta-curves")]
+use pasta_curves::{
+    arithmetic::{Coordinates, CurveAffine, CurveExt},
+    group::{ff, prime::PrimeCurveAffine, Group},
+};
+#[cfg(feature = "pasta-curves")]
+use snafu::Snafu;
+#[cfg(feature = "pasta-curves")]
+use std::convert::TryFrom;
+
+// Bindings of the contracts listed in `build.rs`
+include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+// Curve-generic points with the layout of the abigen structs
+mod sol;
+pub use sol::{SolAffine, SolProjective};
+
+// Conversions from and to arkworks, for the version(s) selected by the
+// `ark-0-3` (default) and `ark-0-4` features. The 0.3 ones are re-exported
+// here.
+#[cfg(feature = "ark-0-3")]
+pub mod ark03;
+#[cfg(feature = "ark-0-3")]
+pub use ark03::*;
+#[cfg(feature = "ark-0-4")]
+pub mod ark04;
 
 // ========================================================
 //
-// pallas curve
+// pasta_curves (and halo2curves, which re-exports them)
 //
 // ========================================================
 
-impl From<ark_pallas::Affine> for PallasAffinePoint {
-    fn from(p: ark_pallas::Affine) -> Self {
-        if p.is_zero() {
-            // Solidity precompile have a different affine repr for VestaAffinePoint of Infinity
-            Self {
-                x: U256::from(0),
-                y: U256::from(0),
-            }
-        } else {
-            Self {
-                x: U256::from_little_endian(&to_bytes!(p.x).unwrap()[..]),
-                y: U256::from_little_endian(&to_bytes!(p.y).unwrap()[..]),
-            }
-        }
-    }
-}
-impl From<ark_pallas::Projective> for PallasProjectivePoint {
-    fn from(p: ark_pallas::Projective) -> Self {
-        if p.is_zero() {
-            // Solidity precompile have a different affine repr for PallasAffinePoint of Infinity
-            Self {
-                x: U256::from(0),
-                y: U256::from(0),
-                z: U256::from(0),
-            }
-        } else {
-            Self {
-                x: U256::from_little_endian(&to_bytes!(p.x).unwrap()[..]),
-                y: U256::from_little_endian(&to_bytes!(p.y).unwrap()[..]),
-                z: U256::from_little_endian(&to_bytes!(p.z).unwrap()[..]),
-            }
-        }
-    }
+/// A Solidity point that is not a point of the curve (or not in canonical
+/// form), which the types of `pasta_curves` can't represent.
+#[cfg(feature = "pasta-curves")]
+#[derive(Debug, Snafu)]
+#[snafu(display("Not a point of {}: {:?}", curve, coordinates))]
+pub struct InvalidPointError {
+    curve: &'static str,
+    coordinates: Vec<U256>,
 }
 
-impl From<(ark_pallas::Fq, ark_pallas::Fq)> for PallasAffinePoint {
-    fn from(p: (ark_pallas::Fq, ark_pallas::Fq)) -> Self {
-        let zero = ark_pallas::Affine::zero();
-        if p.0 == zero.x && p.1 == zero.y {
-            // Solidity repr of infinity/zero
-            Self {
-                x: U256::from(0),
-                y: U256::from(0),
-            }
-        } else {
-            Self {
-                x: U256::from_little_endian(&to_bytes!(p.0).unwrap()[..]),
-                y: U256::from_little_endian(&to_bytes!(p.1).unwrap()[..]),
+#[cfg(feature = "pasta-curves")]
+macro_rules! impl_pasta_conversions {
+    ($curve:ident, $affine:ident, $projective:ident) => {
+        impl From<pasta_curves::$curve::Affine> for $affine {
+            fn from(p: pasta_curves::$curve::Affine) -> Self {
+                let (x, y) = pasta_affine_to_u256(&p);
+                Self { x, y }
             }
         }
-    }
-}
 
-impl From<(ark_pallas::Fq, ark_pallas::Fq, ark_pallas::Fq)> for PallasProjectivePoint {
-    fn from(p: (ark_pallas::Fq, ark_pallas::Fq, ark_pallas::Fq)) -> Self {
-        let zero = ark_pallas::Affine::zero();
-        if p.0 == zero.x && p.1 == zero.y {
-            // Solidity repr of infinity/zero
-            Self {
-                x: U256::from(0),
-                y: U256::from(0),
-                z: U256::from(0),
-            }
-        } else {
-            Self {
-                x: U256::from_little_endian(&to_bytes!(p.0).unwrap()[..]),
-                y: U256::from_little_endian(&to_bytes!(p.1).unwrap()[..]),
-                z: U256::from_little_endian(&to_bytes!(p.2).unwrap()[..]),
+        impl From<pasta_curves::$curve::Point> for $projective {
+            fn from(p: pasta_curves::$curve::Point) -> Self {
+                let (x, y, z) = pasta_projective_to_u256(&p);
+                Self { x, y, z }
             }
         }
-    }
-}
 
-impl From<PallasAffinePoint> for ark_pallas::Affine {
-    fn from(p_sol: PallasAffinePoint) -> Self {
-        if p_sol.x.is_zero() && p_sol.y.is_zero() {
-            Self::zero()
-        } else {
-            Self::new(u256_to_field(p_sol.x), u256_to_field(p_sol.y), false)
-        }
-    }
-}
+        impl TryFrom<$affine> for pasta_curves::$curve::Affine {
+            type Error = InvalidPointError;
 
-impl From<PallasProjectivePoint> for ark_pallas::Projective {
-    fn from(p_sol: PallasProjectivePoint) -> Self {
-        if p_sol.x.is_zero() && p_sol.y.is_zero() && p_sol.z.is_zero() {
-            Self::zero()
-        } else {
-            Self::new(
-                u256_to_field(p_sol.x),
-                u256_to_field(p_sol.y),
-                u256_to_field(p_sol.z),
-            )
-        }
-    }
-}
-
-// ========================================================
-//
-// vesta curve
-//
-// ========================================================
-
-impl From<ark_vesta::Affine> for VestaAffinePoint {
-    fn from(p: ark_vesta::Affine) -> Self {
-        if p.is_zero() {
-            // Solidity precompile have a different affine repr for VestaAffinePoint of Infinity
-            Self {
-                x: U256::from(0),
-                y: U256::from(0),
-            }
-        } else {
-            Self {
-                x: U256::from_little_endian(&to_bytes!(p.x).unwrap()[..]),
-                y: U256::from_little_endian(&to_bytes!(p.y).unwrap()[..]),
+            fn try_from(p_sol: $affine) -> Result<Self, Self::Error> {
+                pasta_affine_from_u256(p_sol.x, p_sol.y).ok_or_else(|| InvalidPointError {
+                    curve: stringify!($curve),
+                    coordinates: vec![p_sol.x, p_sol.y],
+                })
             }
         }
-    }
-}
 
-impl From<ark_vesta::Projective> for VestaProjectivePoint {
-    fn from(p: ark_vesta::Projective) -> Self {
-        if p.is_zero() {
-            // Solidity precompile have a different affine repr for PallasAffinePoint of Infinity
-            Self {
-                x: U256::from(0),
-                y: U256::from(0),
-                z: U256::from(0),
-            }
-        } else {
-            Self {
-                x: U256::from_little_endian(&to_bytes!(p.x).unwrap()[..]),
-                y: U256::from_little_endian(&to_bytes!(p.y).unwrap()[..]),
-                z: U256::from_little_endian(&to_bytes!(p.z).unwrap()[..]),
-            }
-        }
-    }
-}
-
-impl From<(ark_vesta::Fq, ark_vesta::Fq)> for VestaAffinePoint {
-    fn from(p: (ark_vesta::Fq, ark_vesta::Fq)) -> Self {
-        let zero = ark_vesta::Affine::zero();
-        if p.0 == zero.x && p.1 == zero.y {
-            // Solidity repr of infinity/zero
-            Self {
-                x: U256::from(0),
-                y: U256::from(0),
-            }
-        } else {
-            Self {
-                x: U256::from_little_endian(&to_bytes!(p.0).unwrap()[..]),
-                y: U256::from_little_endian(&to_bytes!(p.1).unwrap()[..]),
-            }
-        }
-    }
-}
+        impl TryFrom<$projective> for pasta_curves::$curve::Point {
+            type Error = InvalidPointError;
 
-impl From<(ark_vesta::Fq, ark_vesta::Fq, ark_vesta::Fq)> for VestaProjectivePoint {
-    fn from(p: (ark_vesta::Fq, ark_vesta::Fq, ark_vesta::Fq)) -> Self {
-        let zero = ark_vesta::Affine::zero();
-        if p.0 == zero.x && p.1 == zero.y {
-            // Solidity repr of infinity/zero
-            Self {
-                x: U256::from(0),
-                y: U256::from(0),
-                z: U256::from(0),
+            fn try_from(p_sol: $projective) -> Result<Self, Self::Error> {
+                pasta_projective_from_u256(p_sol.x, p_sol.y, p_sol.z).ok_or_else(|| {
+                    InvalidPointError {
+                        curve: stringify!($curve),
+                        coordinates: vec![p_sol.x, p_sol.y, p_sol.z],
+                    }
+                })
             }
-        } else {
-            Self {
-                x: U256::from_little_endian(&to_bytes!(p.0).unwrap()[..]),
-                y: U256::from_little_endian(&to_bytes!(p.1).unwrap()[..]),
-                z: U256::from_little_endian(&to_bytes!(p.2).unwrap()[..]),
-            }
-        }
-    }
-}
-
-impl From<VestaAffinePoint> for ark_vesta::Affine {
-    fn from(p_sol: VestaAffinePoint) -> Self {
-        if p_sol.x.is_zero() && p_sol.y.is_zero() {
-            Self::zero()
-        } else {
-            Self::new(u256_to_field(p_sol.x), u256_to_field(p_sol.y), false)
-        }
-    }
-}
-
-impl From<VestaProjectivePoint> for ark_vesta::Projective {
-    fn from(p_sol: VestaProjectivePoint) -> Self {
-        if p_sol.x.is_zero() && p_sol.y.is_zero() && p_sol.z.is_zero() {
-            Self::zero()
-        } else {
-            Self::new(
-                u256_to_field(p_sol.x),
-                u256_to_field(p_sol.y),
-                u256_to_field(p_sol.z),
-            )
         }
-    }
-}
-
-/// convert a field element (at most BigInteger256).
-pub fn field_to_u256<F: PrimeField>(f: F) -> U256 {
-    if F::size_in_bits() > 256 {
-        panic!("Don't support field size larger than 256 bits.");
-    }
-    U256::from_little_endian(&to_bytes!(&f).unwrap())
-}
-
-/// convert a U256 to a field element.
-pub fn u256_to_field<F: PrimeField>(v: U256) -> F {
-    let mut bytes = vec![0u8; 32];
-    v.to_little_endian(&mut bytes);
-    F::from_le_bytes_mod_order(&bytes)
+    };
+}
+
+#[cfg(feature = "pasta-curves")]
+impl_pasta_conversions!(pallas, PallasAffinePoint, PallasProjectivePoint);
+#[cfg(feature = "pasta-curves")]
+impl_pasta_conversions!(vesta, VestaAffinePoint, VestaProjectivePoint);
+
+/// convert a `pasta_curves` field element.
+#[cfg(feature = "pasta-curves")]
+pub fn pasta_field_to_u256<F: ff::PrimeField<Repr = [u8; 32]>>(f: F) -> U256 {
+    U256::from_little_endian(&f.to_repr())
+}
+
+/// convert a U256 to a `pasta_curves` field element, `None` if it is not
+/// below the modulus (unlike `u256_to_field`, which reduces it).
+#[cfg(feature = "pasta-curves")]
+pub fn u256_to_pasta_field<F: ff::PrimeField<Repr = [u8; 32]>>(v: U256) -> Option<F> {
+    let mut repr = [0u8; 32];
+    v.to_little_endian(&mut repr);
+    F::from_repr(repr).into()
+}
+
+#[cfg(feature = "pasta-curves")]
+fn pasta_affine_to_u256<C: CurveAffine>(p: &C) -> (U256, U256)
+where
+    C::Base: ff::PrimeField<Repr = [u8; 32]>,
+{
+    // the point of infinity has no coordinates, and is (0, 0) in Solidity
+    Option::<Coordinates<C>>::from(p.coordinates())
+        .map(|c| (pasta_field_to_u256(*c.x()), pasta_field_to_u256(*c.y())))
+        .unwrap_or_default()
+}
+
+#[cfg(feature = "pasta-curves")]
+fn pasta_projective_to_u256<C: CurveExt>(p: &C) -> (U256, U256, U256)
+where
+    C::Base: ff::PrimeField<Repr = [u8; 32]>,
+{
+    if bool::from(p.is_identity()) {
+        return Default::default();
+    }
+    // both use Jacobian coordinates
+    let (x, y, z) = p.jacobian_coordinates();
+    (
+        pasta_field_to_u256(x),
+        pasta_field_to_u256(y),
+        pasta_field_to_u256(z),
+    )
+}
+
+#[cfg(feature = "pasta-curves")]
+fn pasta_affine_from_u256<C: CurveAffine>(x: U256, y: U256) -> Option<C>
+where
+    C::Base: ff::PrimeField<Repr = [u8; 32]>,
+{
+    if x.is_zero() && y.is_zero() {
+        return Some(C::identity());
+    }
+    Option::from(C::from_xy(u256_to_pasta_field(x)?, u256_to_pasta_field(y)?))
+}
+
+#[cfg(feature = "pasta-curves")]
+fn pasta_projective_from_u256<C: CurveExt>(x: U256, y: U256, z: U256) -> Option<C>
+where
+    C::Base: ff::PrimeField<Repr = [u8; 32]>,
+{
+    if x.is_zero() && y.is_zero() && z.is_zero() {
+        return Some(C::identity());
+    }
+    Option::from(C::new_jacobian(
+        u256_to_pasta_field(x)?,
+        u256_to_pasta_field(y)?,
+        u256_to_pasta_field(z)?,
+    ))
 }
 
 /// a helper trait to help with fully-qualified generic into syntax:
@@ -251,61 +173,3 @@ pub trait GenericInto {
 
 // blanket implementation
 impl<T: ?Sized> GenericInto for T {}
-
-#[cfg(test)]
-mod test {
-    use super::*;
-    use ark_ff::field_new;
-    use ark_pallas::{Affine, Fq, Fr};
-    use ark_std::UniformRand;
-
-    #[test]
-    fn field_types_conversion() {
-        let rng = &mut ark_std::test_rng();
-        let f1 = Fr::rand(rng);
-        let f2 = Fq::rand(rng);
-        // trivial test, prevent accidental change to the function
-        assert_eq!(
-            field_to_u256(f1),
-            U256::from_little_endian(&to_bytes!(f1).unwrap())
-        );
-        assert_eq!(
-            field_to_u256(f2),
-            U256::from_little_endian(&to_bytes!(f2).unwrap())
-        );
-
-        assert_eq!(f1, u256_to_field(field_to_u256(f1)));
-        assert_eq!(f2, u256_to_field(field_to_u256(f2)));
-    }
-
-    #[test]
-    fn group_types_conversion() {
-        // special case: point of infinity (zero)
-        let p1 = Affine::default();
-        let p1_sol: PallasAffinePoint = p1.into();
-        assert_eq!(p1_sol.x, U256::from(0));
-        assert_eq!(p1_sol.y, U256::from(0));
-        assert_eq!(p1, p1_sol.generic_into::<Affine>());
-
-        // a point (not on the curve, which doesn't matter since we only check conversion)
-        let p2 = Affine::new(field_new!(Fq, "12345"), field_new!(Fq, "2"), false);
-        let p2_sol: PallasAffinePoint = p2.into();
-        assert_eq!(p2_sol.x, U256::from(12345));
-        assert_eq!(p2_sol.y, U256::from(2));
-        assert_eq!(p2, p2_sol.generic_into::<Affine>());
-
-        // special case: point of infinity (zero)
-        let p1 = ark_vesta::Affine::default();
-        let p1_sol: VestaAffinePoint = p1.into();
-        assert_eq!(p1_sol.x, U256::from(0));
-        assert_eq!(p1_sol.y, U256::from(0));
-        assert_eq!(p1, p1_sol.generic_into::<ark_vesta::Affine>());
-
-        // a point (not on the curve, which doesn't matter since we only check conversion)
-        let p2 = ark_vesta::Affine::new(field_new!(Fr, "12345"), field_new!(Fr, "2"), false);
-        let p2_sol: VestaAffinePoint = p2.into();
-        assert_eq!(p2_sol.x, U256::from(12345));
-        assert_eq!(p2_sol.y, U256::from(2));
-        assert_eq!(p2, p2_sol.generic_into::<ark_vesta::Affine>());
-    }
-}
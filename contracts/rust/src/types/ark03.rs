@@ -0,0 +1,356 @@
+//! Conversions between the Solidity types and arkworks 0.3 (`ark-pallas`,
+//! `ark-vesta`, `ark-ff` 0.3), with the `ark-0-3` feature.
+use super::{
+    PallasAffinePoint, PallasProjectivePoint, SolAffine, SolProjective, VestaAffinePoint,
+    VestaProjectivePoint,
+};
+use ark_ec::{
+    short_weierstrass_jacobian::{GroupAffine, GroupProjective},
+    SWModelParameters,
+};
+use ark_ff::{to_bytes, BigInteger, PrimeField, Zero};
+use ethers::prelude::*;
+
+// ========================================================
+//
+// any short Weierstrass curve
+//
+// ========================================================
+
+impl<P: SWModelParameters> From<GroupAffine<P>> for SolAffine<P>
+where
+    P::BaseField: PrimeField,
+{
+    fn from(p: GroupAffine<P>) -> Self {
+        if p.is_zero() {
+            // Solidity repr of infinity/zero
+            Self::infinity()
+        } else {
+            Self::new(field_to_u256(p.x), field_to_u256(p.y))
+        }
+    }
+}
+
+impl<P: SWModelParameters> From<GroupProjective<P>> for SolProjective<P>
+where
+    P::BaseField: PrimeField,
+{
+    fn from(p: GroupProjective<P>) -> Self {
+        if p.is_zero() {
+            Self::infinity()
+        } else {
+            Self::new(field_to_u256(p.x), field_to_u256(p.y), field_to_u256(p.z))
+        }
+    }
+}
+
+impl<P: SWModelParameters> From<SolAffine<P>> for GroupAffine<P>
+where
+    P::BaseField: PrimeField,
+{
+    fn from(p_sol: SolAffine<P>) -> Self {
+        if p_sol.is_infinity() {
+            Self::zero()
+        } else {
+            Self::new(u256_to_field(p_sol.x), u256_to_field(p_sol.y), false)
+        }
+    }
+}
+
+impl<P: SWModelParameters> From<SolProjective<P>> for GroupProjective<P>
+where
+    P::BaseField: PrimeField,
+{
+    fn from(p_sol: SolProjective<P>) -> Self {
+        if p_sol.is_infinity() {
+            Self::zero()
+        } else {
+            Self::new(
+                u256_to_field(p_sol.x),
+                u256_to_field(p_sol.y),
+                u256_to_field(p_sol.z),
+            )
+        }
+    }
+}
+
+// ========================================================
+//
+// pallas and vesta, through `SolAffine` and `SolProjective`
+//
+// ========================================================
+
+macro_rules! impl_ark03_conversions {
+    ($curve:ident, $parameters:ident, $affine:ident, $projective:ident) => {
+        impl From<$curve::Affine> for $affine {
+            fn from(p: $curve::Affine) -> Self {
+                SolAffine::from(p).into()
+            }
+        }
+
+        impl From<$curve::Projective> for $projective {
+            fn from(p: $curve::Projective) -> Self {
+                SolProjective::from(p).into()
+            }
+        }
+
+        impl From<($curve::Fq, $curve::Fq)> for $affine {
+            fn from(p: ($curve::Fq, $curve::Fq)) -> Self {
+                let zero = $curve::Affine::zero();
+                if p.0 == zero.x && p.1 == zero.y {
+                    // Solidity repr of infinity/zero
+                    SolAffine::<$curve::$parameters>::infinity().into()
+                } else {
+                    Self {
+                        x: field_to_u256(p.0),
+                        y: field_to_u256(p.1),
+                    }
+                }
+            }
+        }
+
+        impl From<($curve::Fq, $curve::Fq, $curve::Fq)> for $projective {
+            fn from(p: ($curve::Fq, $curve::Fq, $curve::Fq)) -> Self {
+                let zero = $curve::Affine::zero();
+                if p.0 == zero.x && p.1 == zero.y {
+                    // Solidity repr of infinity/zero
+                    SolProjective::<$curve::$parameters>::infinity().into()
+                } else {
+                    Self {
+                        x: field_to_u256(p.0),
+                        y: field_to_u256(p.1),
+                        z: field_to_u256(p.2),
+                    }
+                }
+            }
+        }
+
+        impl From<$affine> for $curve::Affine {
+            fn from(p_sol: $affine) -> Self {
+                SolAffine::<$curve::$parameters>::from(p_sol).into()
+            }
+        }
+
+        impl From<$projective> for $curve::Projective {
+            fn from(p_sol: $projective) -> Self {
+                SolProjective::<$curve::$parameters>::from(p_sol).into()
+            }
+        }
+    };
+}
+
+impl_ark03_conversions!(
+    ark_pallas,
+    PallasParameters,
+    PallasAffinePoint,
+    PallasProjectivePoint
+);
+impl_ark03_conversions!(
+    ark_vesta,
+    VestaParameters,
+    VestaAffinePoint,
+    VestaProjectivePoint
+);
+
+// ========================================================
+//
+// sign conventions of y-coordinates
+//
+// ========================================================
+
+/// arkworks' sign, `y < -y`, as `isYNegative` of the Solidity libraries.
+pub fn is_y_negative<F: PrimeField>(y: F) -> bool {
+    y < -y
+}
+
+/// The flag of arkworks' compressed points, `y > -y` (the `greatest`
+/// argument of `get_point_from_x`), as `isYGreatest`.
+pub fn is_y_greatest<F: PrimeField>(y: F) -> bool {
+    y > -y
+}
+
+/// Mina's convention, as `isYEven`: the commitment of a Schnorr signature has
+/// an even y, compressed keys store `is_odd`.
+pub fn is_y_even<F: PrimeField>(y: F) -> bool {
+    y.into_repr().is_even()
+}
+
+/// Zcash's sign bit, the parity of y stored in the most significant bit of the
+/// encoding of x (as in `pasta_curves`), as `yParity`.
+pub fn y_parity<F: PrimeField>(y: F) -> u8 {
+    y.into_repr().is_odd() as u8
+}
+
+/// convert a field element (at most BigInteger256).
+pub fn field_to_u256<F: PrimeField>(f: F) -> U256 {
+    if F::size_in_bits() > 256 {
+        panic!("Don't support field size larger than 256 bits.");
+    }
+    U256::from_little_endian(&to_bytes!(&f).unwrap())
+}
+
+/// convert a U256 to a field element.
+pub fn u256_to_field<F: PrimeField>(v: U256) -> F {
+    let mut bytes = vec![0u8; 32];
+    v.to_little_endian(&mut bytes);
+    F::from_le_bytes_mod_order(&bytes)
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        deployments::deploy_or_reuse,
+        ethereum::{artifact_path, get_funded_deployer},
+        types::GenericInto,
+    };
+    use anyhow::Result;
+    use ark_ec::{AffineCurve, ProjectiveCurve};
+    use ark_ff::{field_new, FpParameters, One};
+    use ark_pallas::{Affine, Fq, Fr, Projective};
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::UniformRand;
+
+    #[test]
+    fn field_types_conversion() {
+        let rng = &mut ark_std::test_rng();
+        let f1 = Fr::rand(rng);
+        let f2 = Fq::rand(rng);
+        // trivial test, prevent accidental change to the function
+        assert_eq!(
+            field_to_u256(f1),
+            U256::from_little_endian(&to_bytes!(f1).unwrap())
+        );
+        assert_eq!(
+            field_to_u256(f2),
+            U256::from_little_endian(&to_bytes!(f2).unwrap())
+        );
+
+        assert_eq!(f1, u256_to_field(field_to_u256(f1)));
+        assert_eq!(f2, u256_to_field(field_to_u256(f2)));
+    }
+
+    #[test]
+    fn group_types_conversion() {
+        // special case: point of infinity (zero)
+        let p1 = Affine::default();
+        let p1_sol: PallasAffinePoint = p1.into();
+        assert_eq!(p1_sol.x, U256::from(0));
+        assert_eq!(p1_sol.y, U256::from(0));
+        assert_eq!(p1, p1_sol.generic_into::<Affine>());
+
+        // a point (not on the curve, which doesn't matter since we only check conversion)
+        let p2 = Affine::new(field_new!(Fq, "12345"), field_new!(Fq, "2"), false);
+        let p2_sol: PallasAffinePoint = p2.into();
+        assert_eq!(p2_sol.x, U256::from(12345));
+        assert_eq!(p2_sol.y, U256::from(2));
+        assert_eq!(p2, p2_sol.generic_into::<Affine>());
+
+        // special case: point of infinity (zero)
+        let p1 = ark_vesta::Affine::default();
+        let p1_sol: VestaAffinePoint = p1.into();
+        assert_eq!(p1_sol.x, U256::from(0));
+        assert_eq!(p1_sol.y, U256::from(0));
+        assert_eq!(p1, p1_sol.generic_into::<ark_vesta::Affine>());
+
+        // a point (not on the curve, which doesn't matter since we only check conversion)
+        let p2 = ark_vesta::Affine::new(field_new!(Fr, "12345"), field_new!(Fr, "2"), false);
+        let p2_sol: VestaAffinePoint = p2.into();
+        assert_eq!(p2_sol.x, U256::from(12345));
+        assert_eq!(p2_sol.y, U256::from(2));
+        assert_eq!(p2, p2_sol.generic_into::<ark_vesta::Affine>());
+    }
+
+    #[test]
+    fn y_sign_conventions() {
+        let rng = &mut ark_std::test_rng();
+        // (p - 1) / 2, the boundary of arkworks' sign
+        let half = Fq::from_repr(<Fq as PrimeField>::Params::MODULUS_MINUS_ONE_DIV_TWO).unwrap();
+        let mut ys = vec![Fq::zero(), Fq::one(), half, half + Fq::one(), -Fq::one()];
+        ys.extend((0..20).map(|_| Fq::rand(rng)));
+
+        for y in ys {
+            // the flag of arkworks' compressed encoding, in the last byte
+            let p = Affine::new(Fq::rand(rng), y, false);
+            let mut bytes = vec![];
+            p.serialize(&mut bytes).unwrap();
+            assert_eq!(is_y_greatest(y), bytes.last().unwrap() & (1 << 7) != 0);
+            assert_eq!(is_y_negative(y), !y.is_zero() && !is_y_greatest(y));
+
+            // parity of the canonical representative
+            let y_u256 = field_to_u256(y);
+            assert_eq!(is_y_even(y), !y_u256.bit(0));
+            assert_eq!(y_parity(y), y_u256.bit(0) as u8);
+        }
+        assert!(is_y_negative(half) && !is_y_greatest(half));
+        assert!(is_y_greatest(half + Fq::one()));
+
+        // decompression with the flag gives the point back
+        for _ in 0..10 {
+            let p = Projective::rand(rng).into_affine();
+            assert_eq!(Affine::get_point_from_x(p.x, is_y_greatest(p.y)), Some(p));
+        }
+    }
+
+    #[test]
+    fn generic_conversions() {
+        let rng = &mut ark_std::test_rng();
+
+        // the same conversions for both curves, and the abigen structs
+        let p = Projective::rand(rng);
+        let p_sol = SolProjective::from(p);
+        assert_eq!(Projective::from(p_sol), p);
+        assert_eq!(PallasProjectivePoint::from(p), p_sol.into());
+        let p = p.into_affine();
+        assert_eq!(Affine::from(SolAffine::from(p)), p);
+        assert_eq!(PallasAffinePoint::from(p), SolAffine::from(p).into());
+        let q = ark_vesta::Projective::rand(rng).into_affine();
+        assert_eq!(VestaAffinePoint::from(q), SolAffine::from(q).into());
+
+        // special case: point of infinity (zero)
+        assert!(SolAffine::from(Affine::zero()).is_infinity());
+        assert!(SolProjective::from(Projective::zero()).is_infinity());
+        let zero = SolProjective::<ark_pallas::PallasParameters>::infinity();
+        assert!(Projective::from(zero).is_zero());
+    }
+
+    #[tokio::test]
+    async fn sol_points_in_contract_calls() -> Result<()> {
+        let rng = &mut ark_std::test_rng();
+        let client = get_funded_deployer().await?;
+        let path = artifact_path("contracts/mocks/TestVesta.sol:TestVesta")?;
+        let contract = deploy_or_reuse(client, &path, ()).await?;
+
+        // calls without the abigen structs, encoded and decoded through
+        // `SolAffine`
+        let p1 = ark_vesta::Projective::rand(rng).into_affine();
+        let p2 = ark_vesta::Projective::rand(rng).into_affine();
+        let sum: SolAffine<ark_vesta::VestaParameters> = contract
+            .method("affineAdd", (SolAffine::from(p1), SolAffine::from(p2)))?
+            .call()
+            .await?;
+        assert_eq!(ark_vesta::Affine::from(sum), p1 + p2);
+
+        let bases: Vec<ark_vesta::Affine> = (0..3)
+            .map(|_| ark_vesta::Projective::rand(rng).into_affine())
+            .collect();
+        let scalars: Vec<ark_vesta::Fr> = (0..3).map(|_| ark_vesta::Fr::rand(rng)).collect();
+        let expected = bases
+            .iter()
+            .zip(scalars.iter())
+            .fold(ark_vesta::Projective::zero(), |acc, (b, s)| {
+                acc + b.mul(s.into_repr())
+            });
+        let msm: SolAffine<ark_vesta::VestaParameters> = contract
+            .method(
+                "testMultiScalarMul",
+                (
+                    bases.into_iter().map(SolAffine::from).collect::<Vec<_>>(),
+                    scalars.into_iter().map(field_to_u256).collect::<Vec<_>>(),
+                ),
+            )?
+            .call()
+            .await?;
+        assert_eq!(ark_vesta::Affine::from(msm), expected.into_affine());
+        Ok(())
+    }
+}
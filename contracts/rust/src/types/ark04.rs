@@ -0,0 +1,265 @@
+//! Conversions between the Solidity types and arkworks 0.4 (`ark-pallas`,
+//! `ark-vesta`, `ark-ff` 0.4), with the `ark-0-4` feature.
+//!
+//! Like the 0.3 conversions, the point of infinity is `(0, 0)` in affine and
+//! `(0, 0, 0)` in projective coordinates, and projective points are in
+//! Jacobian coordinates on both sides.
+use super::{
+    PallasAffinePoint, PallasProjectivePoint, SolAffine, SolProjective, VestaAffinePoint,
+    VestaProjectivePoint,
+};
+use ark_ec_04::short_weierstrass::{Affine, Projective, SWCurveConfig};
+use ark_ff_04::{BigInteger, PrimeField, Zero};
+use ethers::prelude::*;
+
+impl<P: SWCurveConfig> From<Affine<P>> for SolAffine<P>
+where
+    P::BaseField: PrimeField,
+{
+    fn from(p: Affine<P>) -> Self {
+        if p.infinity {
+            // Solidity repr of infinity/zero
+            Self::infinity()
+        } else {
+            Self::new(field_to_u256(p.x), field_to_u256(p.y))
+        }
+    }
+}
+
+impl<P: SWCurveConfig> From<Projective<P>> for SolProjective<P>
+where
+    P::BaseField: PrimeField,
+{
+    fn from(p: Projective<P>) -> Self {
+        if p.is_zero() {
+            Self::infinity()
+        } else {
+            Self::new(field_to_u256(p.x), field_to_u256(p.y), field_to_u256(p.z))
+        }
+    }
+}
+
+impl<P: SWCurveConfig> From<SolAffine<P>> for Affine<P>
+where
+    P::BaseField: PrimeField,
+{
+    fn from(p_sol: SolAffine<P>) -> Self {
+        if p_sol.is_infinity() {
+            Self::identity()
+        } else {
+            Self::new_unchecked(u256_to_field(p_sol.x), u256_to_field(p_sol.y))
+        }
+    }
+}
+
+impl<P: SWCurveConfig> From<SolProjective<P>> for Projective<P>
+where
+    P::BaseField: PrimeField,
+{
+    fn from(p_sol: SolProjective<P>) -> Self {
+        if p_sol.is_infinity() {
+            Self::zero()
+        } else {
+            Self::new_unchecked(
+                u256_to_field(p_sol.x),
+                u256_to_field(p_sol.y),
+                u256_to_field(p_sol.z),
+            )
+        }
+    }
+}
+
+macro_rules! impl_ark04_conversions {
+    ($curve:ident, $config:ident, $affine:ident, $projective:ident) => {
+        impl From<$curve::Affine> for $affine {
+            fn from(p: $curve::Affine) -> Self {
+                SolAffine::from(p).into()
+            }
+        }
+
+        impl From<$curve::Projective> for $projective {
+            fn from(p: $curve::Projective) -> Self {
+                SolProjective::from(p).into()
+            }
+        }
+
+        impl From<$affine> for $curve::Affine {
+            fn from(p_sol: $affine) -> Self {
+                SolAffine::<$curve::$config>::from(p_sol).into()
+            }
+        }
+
+        impl From<$projective> for $curve::Projective {
+            fn from(p_sol: $projective) -> Self {
+                SolProjective::<$curve::$config>::from(p_sol).into()
+            }
+        }
+    };
+}
+
+impl_ark04_conversions!(
+    ark_pallas_04,
+    PallasConfig,
+    PallasAffinePoint,
+    PallasProjectivePoint
+);
+impl_ark04_conversions!(
+    ark_vesta_04,
+    VestaConfig,
+    VestaAffinePoint,
+    VestaProjectivePoint
+);
+
+/// arkworks' sign, `y < -y`, as `isYNegative` of the Solidity libraries.
+pub fn is_y_negative<F: PrimeField>(y: F) -> bool {
+    y < -y
+}
+
+/// The flag of arkworks' compressed points, `y > -y`, as `isYGreatest`.
+pub fn is_y_greatest<F: PrimeField>(y: F) -> bool {
+    y > -y
+}
+
+/// Mina's convention, as `isYEven`.
+pub fn is_y_even<F: PrimeField>(y: F) -> bool {
+    y.into_bigint().is_even()
+}
+
+/// Zcash's sign bit, the parity of y, as `yParity`.
+pub fn y_parity<F: PrimeField>(y: F) -> u8 {
+    y.into_bigint().is_odd() as u8
+}
+
+/// convert a field element (at most 256 bits).
+pub fn field_to_u256<F: PrimeField>(f: F) -> U256 {
+    if F::MODULUS_BIT_SIZE > 256 {
+        panic!("Don't support field size larger than 256 bits.");
+    }
+    U256::from_little_endian(&f.into_bigint().to_bytes_le())
+}
+
+/// convert a U256 to a field element.
+pub fn u256_to_field<F: PrimeField>(v: U256) -> F {
+    let mut bytes = vec![0u8; 32];
+    v.to_little_endian(&mut bytes);
+    F::from_le_bytes_mod_order(&bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        deployments::deploy_or_reuse,
+        ethereum::{artifact_path, get_funded_deployer},
+        multicall::Batch,
+        types::TestPallas,
+    };
+    use anyhow::Result;
+    use ark_ec_04::{AffineRepr, CurveGroup};
+    use ark_ff_04::One;
+    use ark_pallas_04::{Affine, Fq, Fr, Projective};
+    use ark_std_04::UniformRand;
+
+    #[test]
+    fn field_types_conversion() {
+        let rng = &mut ark_std_04::test_rng();
+        let f1 = Fr::rand(rng);
+        let f2 = Fq::rand(rng);
+        assert_eq!(f1, u256_to_field(field_to_u256(f1)));
+        assert_eq!(f2, u256_to_field(field_to_u256(f2)));
+        assert_eq!(
+            field_to_u256(-Fq::one()) + 1,
+            U256::from_dec_str(
+                "28948022309329048855892746252171976963363056481941560715954676764349967630337"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn group_types_conversion() {
+        let rng = &mut ark_std_04::test_rng();
+
+        // special case: point of infinity (zero)
+        let zero = Affine::identity();
+        let zero_sol: PallasAffinePoint = zero.into();
+        assert_eq!((zero_sol.x, zero_sol.y), (U256::zero(), U256::zero()));
+        assert_eq!(Affine::from(zero_sol), zero);
+        let zero_sol: PallasProjectivePoint = Projective::zero().into();
+        assert_eq!(
+            (zero_sol.x, zero_sol.y, zero_sol.z),
+            (U256::zero(), U256::zero(), U256::zero())
+        );
+        assert!(Projective::from(zero_sol).is_zero());
+
+        for _ in 0..10 {
+            let p = Projective::rand(rng);
+            let p_sol: PallasProjectivePoint = p.into();
+            assert_eq!(p_sol.z, field_to_u256(p.z));
+            assert_eq!(Projective::from(p_sol), p);
+            let p = p.into_affine();
+            let p_sol: PallasAffinePoint = p.into();
+            assert_eq!(p_sol.x, field_to_u256(p.x));
+            assert_eq!(Affine::from(p_sol), p);
+            assert_eq!(Affine::from(SolAffine::from(p)), p);
+
+            let p = ark_vesta_04::Projective::rand(rng).into_affine();
+            assert_eq!(ark_vesta_04::Affine::from(VestaAffinePoint::from(p)), p);
+        }
+    }
+
+    // with both features, the two versions agree through the Solidity types
+    #[cfg(feature = "ark-0-3")]
+    #[test]
+    fn same_as_ark03() {
+        use ark_ec::{AffineCurve, ProjectiveCurve};
+        use ark_ff::PrimeField as _;
+
+        let g03 = ark_pallas::Affine::prime_subgroup_generator();
+        let g04 = Affine::generator();
+        for k in [1u64, 2, 3, 12345] {
+            let p03 = g03.mul(ark_pallas::Fr::from(k).into_repr()).into_affine();
+            let p04 = (g04 * Fr::from(k)).into_affine();
+            assert_eq!(PallasAffinePoint::from(p03), PallasAffinePoint::from(p04));
+        }
+
+        let rng = &mut ark_std_04::test_rng();
+        for _ in 0..10 {
+            let y = Fq::rand(rng);
+            let y03: ark_pallas::Fq = crate::types::ark03::u256_to_field(field_to_u256(y));
+            assert_eq!(crate::types::ark03::field_to_u256(y03), field_to_u256(y));
+            assert_eq!(crate::types::ark03::is_y_negative(y03), is_y_negative(y));
+            assert_eq!(crate::types::ark03::y_parity(y03), y_parity(y));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_contract_arithmetic() -> Result<()> {
+        let rng = &mut ark_std_04::test_rng();
+        let client = get_funded_deployer().await?;
+        let path = artifact_path("contracts/mocks/TestPallas.sol:TestPallas")?;
+        let contract = deploy_or_reuse(client.clone(), &path, ()).await?;
+        let contract = TestPallas::new(contract.address(), client.clone());
+
+        let mut add = Batch::new(client.clone());
+        let mut add_expected: Vec<PallasAffinePoint> = vec![];
+        let mut mul = Batch::new(client);
+        let mut mul_expected: Vec<PallasAffinePoint> = vec![];
+        for _ in 0..20 {
+            let p1 = Projective::rand(rng).into_affine();
+            let p2 = Projective::rand(rng).into_affine();
+            let s = Fr::rand(rng);
+            add.add(contract.affine_add(p1.into(), p2.into()));
+            add_expected.push((p1 + p2).into_affine().into());
+            mul.add(contract.affine_scalar_mul(p1.into(), field_to_u256(s)));
+            mul_expected.push((p1 * s).into_affine().into());
+        }
+        for (res, expected) in add.call_all().await?.into_iter().zip(add_expected) {
+            assert_eq!(PallasAffinePoint::from(res), expected);
+        }
+        for (res, expected) in mul.call_all().await?.into_iter().zip(mul_expected) {
+            assert_eq!(PallasAffinePoint::from(res), expected);
+        }
+        Ok(())
+    }
+}
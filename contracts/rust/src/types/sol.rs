@@ -0,0 +1,294 @@
+//! Curve-generic Solidity points: `SolAffine<C>` and `SolProjective<C>` have
+//! the layout of the `AffinePoint` and `ProjectivePoint` structs of every curve
+//! library, `(uint256, uint256)` and `(uint256, uint256, uint256)` with the
+//! point of infinity at zero. The parameter `C` only tags the curve: the
+//! arkworks conversions (see `ark03` and `ark04`) are implemented once for
+//! every short Weierstrass curve with a base field of at most 256 bits.
+//!
+//! They are `Tokenizable`, hence `Tokenize`/`Detokenize`, so they can be
+//! passed to and returned from any contract call with that struct layout, and
+//! convert from and to the abigen structs of their curve, e.g.
+//! `PallasAffinePoint` for `SolAffine<ark_pallas::PallasParameters>` or
+//! `SolAffine<ark_pallas_04::PallasConfig>`.
+use super::{PallasAffinePoint, PallasProjectivePoint, VestaAffinePoint, VestaProjectivePoint};
+use ethers::{
+    abi::{InvalidOutputType, Token, Tokenizable, TokenizableItem},
+    types::U256,
+};
+use std::{fmt, marker::PhantomData};
+
+/// An affine point of the curve `C`, `(0, 0)` for the point of infinity.
+pub struct SolAffine<C> {
+    pub x: U256,
+    pub y: U256,
+    _curve: PhantomData<fn() -> C>,
+}
+
+/// A projective point of the curve `C` in Jacobian coordinates, `(0, 0, 0)`
+/// for the point of infinity.
+pub struct SolProjective<C> {
+    pub x: U256,
+    pub y: U256,
+    pub z: U256,
+    _curve: PhantomData<fn() -> C>,
+}
+
+impl<C> SolAffine<C> {
+    pub fn new(x: U256, y: U256) -> Self {
+        Self {
+            x,
+            y,
+            _curve: PhantomData,
+        }
+    }
+
+    /// The Solidity repr of the point of infinity.
+    pub fn infinity() -> Self {
+        Self::new(U256::zero(), U256::zero())
+    }
+
+    pub fn is_infinity(&self) -> bool {
+        self.x.is_zero() && self.y.is_zero()
+    }
+}
+
+impl<C> SolProjective<C> {
+    pub fn new(x: U256, y: U256, z: U256) -> Self {
+        Self {
+            x,
+            y,
+            z,
+            _curve: PhantomData,
+        }
+    }
+
+    /// The Solidity repr of the point of infinity.
+    pub fn infinity() -> Self {
+        Self::new(U256::zero(), U256::zero(), U256::zero())
+    }
+
+    pub fn is_infinity(&self) -> bool {
+        self.x.is_zero() && self.y.is_zero() && self.z.is_zero()
+    }
+}
+
+// implemented by hand, the derives would require `C` to implement them
+
+impl<C> Clone for SolAffine<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for SolAffine<C> {}
+
+impl<C> PartialEq for SolAffine<C> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.x, self.y) == (other.x, other.y)
+    }
+}
+
+impl<C> Eq for SolAffine<C> {}
+
+impl<C> Default for SolAffine<C> {
+    fn default() -> Self {
+        Self::infinity()
+    }
+}
+
+impl<C> fmt::Debug for SolAffine<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SolAffine")
+            .field("x", &self.x)
+            .field("y", &self.y)
+            .finish()
+    }
+}
+
+impl<C> Clone for SolProjective<C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<C> Copy for SolProjective<C> {}
+
+impl<C> PartialEq for SolProjective<C> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.x, self.y, self.z) == (other.x, other.y, other.z)
+    }
+}
+
+impl<C> Eq for SolProjective<C> {}
+
+impl<C> Default for SolProjective<C> {
+    fn default() -> Self {
+        Self::infinity()
+    }
+}
+
+impl<C> fmt::Debug for SolProjective<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SolProjective")
+            .field("x", &self.x)
+            .field("y", &self.y)
+            .field("z", &self.z)
+            .finish()
+    }
+}
+
+/// The coordinates of a tuple of `n` uint256.
+fn uints_from_token(token: Token, n: usize) -> Result<Vec<U256>, InvalidOutputType> {
+    match token {
+        Token::Tuple(tokens) if tokens.len() == n => {
+            tokens.into_iter().map(U256::from_token).collect()
+        }
+        token => Err(InvalidOutputType(format!(
+            "Expected a tuple of {} uint256, got {:?}",
+            n, token
+        ))),
+    }
+}
+
+impl<C> Tokenizable for SolAffine<C> {
+    fn from_token(token: Token) -> Result<Self, InvalidOutputType> {
+        let coordinates = uints_from_token(token, 2)?;
+        Ok(Self::new(coordinates[0], coordinates[1]))
+    }
+
+    fn into_token(self) -> Token {
+        Token::Tuple(vec![Token::Uint(self.x), Token::Uint(self.y)])
+    }
+}
+
+impl<C> TokenizableItem for SolAffine<C> {}
+
+impl<C> Tokenizable for SolProjective<C> {
+    fn from_token(token: Token) -> Result<Self, InvalidOutputType> {
+        let coordinates = uints_from_token(token, 3)?;
+        Ok(Self::new(coordinates[0], coordinates[1], coordinates[2]))
+    }
+
+    fn into_token(self) -> Token {
+        Token::Tuple(vec![
+            Token::Uint(self.x),
+            Token::Uint(self.y),
+            Token::Uint(self.z),
+        ])
+    }
+}
+
+impl<C> TokenizableItem for SolProjective<C> {}
+
+// the abigen structs have the same layout, only for the tags of their curve
+
+macro_rules! impl_abigen_conversions {
+    ($curve:path, $affine:ident, $projective:ident) => {
+        impl From<SolAffine<$curve>> for $affine {
+            fn from(p: SolAffine<$curve>) -> Self {
+                Self { x: p.x, y: p.y }
+            }
+        }
+
+        impl From<$affine> for SolAffine<$curve> {
+            fn from(p: $affine) -> Self {
+                Self::new(p.x, p.y)
+            }
+        }
+
+        impl From<SolProjective<$curve>> for $projective {
+            fn from(p: SolProjective<$curve>) -> Self {
+                Self {
+                    x: p.x,
+                    y: p.y,
+                    z: p.z,
+                }
+            }
+        }
+
+        impl From<$projective> for SolProjective<$curve> {
+            fn from(p: $projective) -> Self {
+                Self::new(p.x, p.y, p.z)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "ark-0-3")]
+impl_abigen_conversions!(
+    ark_pallas::PallasParameters,
+    PallasAffinePoint,
+    PallasProjectivePoint
+);
+#[cfg(feature = "ark-0-3")]
+impl_abigen_conversions!(
+    ark_vesta::VestaParameters,
+    VestaAffinePoint,
+    VestaProjectivePoint
+);
+#[cfg(feature = "ark-0-4")]
+impl_abigen_conversions!(
+    ark_pallas_04::PallasConfig,
+    PallasAffinePoint,
+    PallasProjectivePoint
+);
+#[cfg(feature = "ark-0-4")]
+impl_abigen_conversions!(
+    ark_vesta_04::VestaConfig,
+    VestaAffinePoint,
+    VestaProjectivePoint
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ethers::abi::{Detokenize, Tokenize};
+
+    struct Curve;
+
+    #[test]
+    fn tokenization() {
+        let p = SolAffine::<Curve>::new(U256::from(1), U256::from(2));
+        assert_eq!(
+            p.into_token(),
+            Token::Tuple(vec![Token::Uint(U256::from(1)), Token::Uint(U256::from(2))])
+        );
+        assert_eq!(SolAffine::<Curve>::from_tokens(p.into_tokens()).unwrap(), p);
+
+        let p = SolProjective::<Curve>::new(U256::from(1), U256::from(2), U256::from(3));
+        assert_eq!(
+            SolProjective::<Curve>::from_tokens(p.into_tokens()).unwrap(),
+            p
+        );
+        // arrays of points, as taken by `multiScalarMul`
+        let points = vec![p, SolProjective::infinity()];
+        assert_eq!(
+            Vec::<SolProjective<Curve>>::from_token(points.clone().into_token()).unwrap(),
+            points
+        );
+
+        // wrong layouts
+        assert!(SolAffine::<Curve>::from_tokens(p.into_tokens()).is_err());
+        assert!(SolAffine::<Curve>::from_token(Token::Uint(U256::one())).is_err());
+        assert!(SolAffine::<Curve>::from_token(Token::Tuple(vec![
+            Token::Uint(U256::one()),
+            Token::Bool(true)
+        ]))
+        .is_err());
+    }
+
+    #[cfg(feature = "ark-0-3")]
+    #[test]
+    fn abigen_conversions() {
+        use ark_pallas::PallasParameters;
+        use ark_vesta::VestaParameters;
+
+        let p = SolAffine::<PallasParameters>::new(U256::from(1), U256::from(2));
+        let p_sol = PallasAffinePoint::from(p);
+        assert_eq!((p_sol.x, p_sol.y), (p.x, p.y));
+        assert_eq!(SolAffine::<PallasParameters>::from(p_sol), p);
+        assert!(SolAffine::<Curve>::default().is_infinity());
+        let zero = VestaProjectivePoint::from(SolProjective::<VestaParameters>::infinity());
+        assert!(SolProjective::<VestaParameters>::from(zero).is_infinity());
+    }
+}
@@ -1,14 +1,18 @@
 use anyhow::Result;
-use ethers::{core::k256::ecdsa::SigningKey, prelude::*};
-use pasta_rust_sandbox::{ethereum::*, types::Greeter};
-use std::path::Path;
+#[cfg(feature = "ark-0-3")]
+use ark_ec::AffineCurve;
+use ethers::abi::Tokenize;
+use ethers::prelude::*;
+#[cfg(feature = "ark-0-3")]
+use pasta_rust_sandbox::types::{field_to_u256, TestLinked};
+use pasta_rust_sandbox::{artifacts::Artifact, ethereum::*, types::Greeter};
+use std::{convert::TryFrom, path::Path, sync::Arc};
 
-async fn deploy_contract() -> Result<Greeter<SignerMiddleware<Provider<Http>, Wallet<SigningKey>>>>
-{
+async fn deploy_contract() -> Result<Greeter<EthMiddleware>> {
     let client = get_funded_deployer().await.unwrap();
     let contract = deploy(
         client.clone(),
-        Path::new("../abi/contracts/Greeter.sol/Greeter"),
+        &artifact_path("contracts/Greeter.sol:Greeter")?,
         ("Initial Greeting".to_string(),),
     )
     .await
@@ -32,3 +36,204 @@ async fn test_basic_contract_transaction() {
     let res: String = contract.greet().call().await.unwrap();
     assert_eq!(res, "Hi!");
 }
+
+// the libraries are checked against arkworks 0.3
+#[cfg(feature = "ark-0-3")]
+#[tokio::test]
+async fn test_library_linking() -> Result<()> {
+    let client = get_funded_deployer().await?;
+    let path = &artifact_path("contracts/mocks/TestLinked.sol:TestLinked")?;
+
+    // libraries are deployed on demand
+    let contract = deploy(client.clone(), path, ()).await?;
+    let contract = TestLinked::new(contract.address(), client.clone());
+
+    let g = ark_pallas::Affine::prime_subgroup_generator();
+    let (x, y) = contract
+        .pallas_scalar_mul_generator(U256::from(2))
+        .call()
+        .await?;
+    assert_eq!((x, y), (field_to_u256((g + g).x), field_to_u256((g + g).y)));
+
+    let g = ark_vesta::Affine::prime_subgroup_generator();
+    let (x, y) = contract
+        .vesta_scalar_mul_generator(U256::from(2))
+        .call()
+        .await?;
+    assert_eq!((x, y), (field_to_u256((g + g).x), field_to_u256((g + g).y)));
+
+    // libraries given by address are linked instead of deployed
+    let pallas_external = deploy(
+        client.clone(),
+        &artifact_path("contracts/libraries/PallasExternal.sol:PallasExternal")?,
+        (),
+    )
+    .await?
+    .address();
+    let mut libraries = LibraryAddresses::new();
+    libraries.insert(
+        "contracts/libraries/PallasExternal.sol:PallasExternal".to_string(),
+        pallas_external,
+    );
+    let contract = deploy_with_libraries(client.clone(), path, (), &libraries).await?;
+    let code = client.get_code(contract.address(), None).await?;
+    assert!(code
+        .as_ref()
+        .windows(20)
+        .any(|w| w == pallas_external.as_bytes()));
+
+    let contract = TestLinked::new(contract.address(), client.clone());
+    let (x, _) = contract
+        .pallas_scalar_mul_generator(U256::from(1))
+        .call()
+        .await?;
+    assert_eq!(
+        x,
+        field_to_u256(ark_pallas::Affine::prime_subgroup_generator().x)
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deploy_tx_types() -> Result<()> {
+    let mut config = DeployConfig::from_env()?;
+    config.funding = ethers::utils::parse_ether("0.5")?;
+    let client = get_funded_deployer_with_config(&config).await?;
+    let path = &artifact_path("contracts/Greeter.sol:Greeter")?;
+
+    for tx_type in [TxType::Legacy, TxType::Eip1559] {
+        config.tx_type = tx_type;
+        let contract =
+            deploy_with_config(client.clone(), path, ("Hello".to_string(),), &config).await?;
+        let contract = Greeter::new(contract.address(), client.clone());
+        assert_eq!(contract.greet().call().await?, "Hello");
+    }
+
+    // a gas limit below the intrinsic gas of the deployment must fail
+    config.gas_limit = Some(U256::from(21_000));
+    assert!(
+        deploy_with_config(client.clone(), path, ("Hello".to_string(),), &config)
+            .await
+            .is_err()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_concurrent_accounts() -> Result<()> {
+    let clients = futures::future::try_join_all((0..8).map(|_| get_funded_deployer())).await?;
+    let mut addresses: Vec<Address> = clients
+        .iter()
+        .map(|c| c.default_sender().unwrap())
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+    assert_eq!(addresses.len(), clients.len());
+
+    // every account sends several transactions at once, relying on its own
+    // nonce manager
+    let sends = clients.iter().flat_map(|client| {
+        (0..3).map(move |_| async move {
+            let tx = TransactionRequest::new()
+                .to(client.default_sender().unwrap())
+                .value(1u64);
+            client
+                .send_transaction(tx, None)
+                .await?
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Failed to get TX receipt"))
+        })
+    });
+    let receipts = futures::future::try_join_all(sends).await?;
+    assert_eq!(receipts.len(), 24);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_errors() -> Result<()> {
+    assert!(matches!(
+        artifact_path("Greeter"),
+        Err(EthereumError::InvalidName { .. })
+    ));
+
+    // missing artifacts fail before any RPC call
+    let client = Arc::new(Provider::<Http>::try_from("http://localhost:8545")?);
+    let err = deploy(
+        client,
+        Path::new("../artifacts/contracts/Missing.sol/Missing.json"),
+        (),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(err, EthereumError::ReadArtifact { .. }));
+    assert!(err.to_string().contains("Missing.json"));
+    Ok(())
+}
+
+#[test]
+fn test_create2_address() {
+    // example 0 of EIP-1014
+    assert_eq!(
+        create2_address(Address::zero(), H256::zero(), &[0]),
+        "0x4D1A2e2bB4F88F0250f26Ffff098B0b30B26BF38"
+            .parse()
+            .unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_create2_deployment() -> Result<()> {
+    let config = DeployConfig::from_env()?;
+    let client = get_funded_deployer().await?;
+    let salt = H256::from_low_u64_be(rand::random());
+    let path = &artifact_path("contracts/Greeter.sol:Greeter")?;
+    let args = ("Hello".to_string(),);
+
+    let contract = deploy_create2(client.clone(), path, args.clone(), salt, &config).await?;
+    let mut init_code = Artifact::load(path)?
+        .bytecode
+        .into_bytes()
+        .unwrap()
+        .as_ref()
+        .to_vec();
+    init_code.extend(ethers::abi::encode(&args.clone().into_tokens()));
+    assert_eq!(
+        contract.address(),
+        create2_address(create2_deployer(), salt, &init_code)
+    );
+    let greeter = Greeter::new(contract.address(), client.clone());
+    assert_eq!(greeter.greet().call().await?, "Hello");
+
+    // deploying again from another account reuses the contract without a tx
+    let other = get_funded_deployer().await?;
+    let sender = other.default_sender().unwrap();
+    let nonce = other.get_transaction_count(sender, None).await?;
+    let again = deploy_create2(other.clone(), path, args, salt, &config).await?;
+    assert_eq!(again.address(), contract.address());
+    assert_eq!(other.get_transaction_count(sender, None).await?, nonce);
+
+    // other constructor arguments or salt give other addresses
+    let bye = deploy_create2(client.clone(), path, ("Bye".to_string(),), salt, &config).await?;
+    assert_ne!(bye.address(), contract.address());
+    Ok(())
+}
+
+#[cfg(feature = "ark-0-3")]
+#[tokio::test]
+async fn test_create2_library_linking() -> Result<()> {
+    let config = DeployConfig::from_env()?;
+    let client = get_funded_deployer().await?;
+    let salt = H256::from_low_u64_be(rand::random());
+
+    // libraries are deployed with CREATE2 as well
+    let path = &artifact_path("contracts/mocks/TestLinked.sol:TestLinked")?;
+    let linked = deploy_create2(client.clone(), path, (), salt, &config).await?;
+    let linked = TestLinked::new(linked.address(), client.clone());
+    let g = ark_pallas::Affine::prime_subgroup_generator();
+    let (x, _) = linked
+        .pallas_scalar_mul_generator(U256::from(1))
+        .call()
+        .await?;
+    assert_eq!(x, field_to_u256(g.x));
+    Ok(())
+}